@@ -30,6 +30,9 @@ mod version;
 /// Each value is parsed and saved as an `u8`, and each definition will
 /// be made available in the macro call site with the provided name.
 ///
+/// All the definitions are also made available as `VERSION_FILE`,
+/// one `NAME=VALUE` per line
+///
 /// # Note
 ///
 /// The provided path will be made relative to the `CARGO_MANIFEST_DIR` of the invoking crate.
@@ -33,6 +33,13 @@ pub fn version(input: TokenStream) -> TokenStream {
         Ok(data) => data,
     };
 
+    //the definitions as read, without comments nor blank lines
+    let file = data
+        .iter()
+        .map(|Definition { name, value }| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+
     let defs = data.into_iter().map(|Definition { name, value }| {
         let name = Ident::new(&name, Span::call_site());
         quote! {
@@ -42,6 +49,8 @@ pub fn version(input: TokenStream) -> TokenStream {
 
     let out = quote! {
         #(#defs )*
+
+        pub const VERSION_FILE: &::core::primitive::str = #file;
     };
 
     out.into()
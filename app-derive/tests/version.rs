@@ -25,4 +25,12 @@ fn version() {
     let _major = APPVERSION_M;
     let _minor = APPVERSION_N;
     let _patch = APPVERSION_P;
+
+    assert_eq!(
+        VERSION_FILE,
+        format!(
+            "APPVERSION_M={}\nAPPVERSION_N={}\nAPPVERSION_P={}",
+            APPVERSION_M, APPVERSION_N, APPVERSION_P
+        )
+    );
 }
@@ -0,0 +1,46 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+#include "rslib.h"
+#include <os.h>
+#include <cx.h>
+#include <string.h>
+
+uint8_t stark_derive_private_key(const uint32_t *path, uint8_t path_len, uint8_t *out) {
+    //some curves derive 64 bytes, Stark256 keys are the first 32
+    uint8_t raw[64];
+    volatile uint8_t err = 1;
+
+    BEGIN_TRY
+    {
+        TRY
+        {
+            os_perso_derive_node_bip32(CX_CURVE_Stark256, path, path_len, raw, NULL);
+            memcpy(out, raw, 32);
+            err = 0;
+        }
+        CATCH_OTHER(e)
+        {
+            err = 1;
+        }
+        FINALLY
+        {
+            explicit_bzero(raw, sizeof(raw));
+        }
+    }
+    END_TRY;
+
+    return err;
+}
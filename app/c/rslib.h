@@ -9,6 +9,9 @@ void zemu_log(const char *buf);
 void
 rs_handle_apdu(volatile uint32_t *flags, volatile uint32_t *tx, uint32_t rx, const uint8_t *buffer, uint16_t bufferLen);
 
+/// Derives the Stark private key of `path` into `out` (32 bytes), returns 0 on success
+uint8_t stark_derive_private_key(const uint32_t *path, uint8_t path_len, uint8_t *out);
+
 /////////////
 
 void view_init();
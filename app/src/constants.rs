@@ -18,6 +18,7 @@
 #[derive(PartialEq, Eq)]
 #[cfg_attr(any(test, feature = "derive-debug"), derive(Debug))]
 pub enum ApduError {
+    MoreDataAvailable = 0x6100,
     ExecutionError = 0x6400,
     WrongLength = 0x6700,
     ApduCodeEmptyBuffer = 0x6982,
@@ -67,6 +68,7 @@ impl std::convert::TryFrom<u16> for ApduError {
 
     fn try_from(value: u16) -> Result<Self, Self::Error> {
         match value {
+            0x6100 => Ok(Self::MoreDataAvailable),
             0x6400 => Ok(Self::ExecutionError),
             0x6700 => Ok(Self::WrongLength),
             0x6982 => Ok(Self::ApduCodeEmptyBuffer),
@@ -100,6 +102,7 @@ pub const BIP32_MAX_LENGTH: usize = 10;
 pub const STARK_SIGN_BUFFER_MIN_LENGTH: usize = 72;
 pub const STARK_BIP32_PATH_0: u32 = 0x8000_0A55;
 pub const STARK_BIP32_PATH_1: u32 = 0xA286_2AD3;
+pub const STARKNET_BIP32_PATH_1: u32 = 0xC741_E9C9;
 
 pub(crate) mod instructions {
     pub const CLA: u8 = 0xFF;
@@ -107,7 +110,20 @@ pub(crate) mod instructions {
     pub const INS_GET_VERSION: u8 = 0x00;
     pub const INS_GET_PUBLIC_KEY: u8 = 0x01;
     pub const INS_SIGN: u8 = 0x02;
+    pub const INS_SIGN_INVOKE: u8 = 0x03;
+    pub const INS_SIGN_INVOKE_V3: u8 = 0x04;
+    pub const INS_SIGN_TYPED_DATA: u8 = 0x05;
+    pub const INS_SIGN_DEPLOY_ACCOUNT: u8 = 0x06;
+    pub const INS_SIGN_DEPLOY_ACCOUNT_V3: u8 = 0x07;
+    pub const INS_SIGN_DECLARE: u8 = 0x08;
+    pub const INS_SIGN_DECLARE_V3: u8 = 0x09;
+    pub const INS_SIGN_STARKEX_ORDER: u8 = 0x0A;
+    pub const INS_SIGN_STARKEX_TRANSFER: u8 = 0x0B;
+    pub const INS_RECOVER_PUBLIC_KEY: u8 = 0x0C;
+    pub const INS_GET_NEXT_CHUNK: u8 = 0x0D;
+    pub const INS_GET_APP_CONFIGURATION: u8 = 0x0E;
     pub const INS_SIGN_FELT: u8 = 0x20;
+    pub const INS_SIGN_FELT_BATCH: u8 = 0x21;
 }
 
 pub use instructions::*;
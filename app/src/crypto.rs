@@ -16,9 +16,29 @@
 use core::{mem::MaybeUninit, ptr::addr_of_mut};
 use std::convert::TryFrom;
 
-use crate::{constants::STARK_SIGN_BUFFER_MIN_LENGTH, sys};
+use crate::sys;
 use sys::{crypto::bip32::BIP32Path, errors::Error};
 
+mod curve;
+pub(crate) mod ecdsa;
+pub(crate) mod felt;
+pub(crate) mod grind;
+pub(crate) mod keccak;
+pub(crate) mod pedersen;
+pub(crate) mod poseidon;
+pub(crate) mod rfc6979;
+pub(crate) mod signature;
+
+pub use curve::{AffinePoint, Scalar};
+pub use ecdsa::{public_key, sign, sign_reduced, verify};
+pub use felt::{Felt, U256};
+pub use grind::{grind_key, private_key_from_eth_signature};
+pub use keccak::{keccak256, starknet_keccak, Keccak256};
+pub use pedersen::{compute_hash_on_elements, pedersen_hash, PedersenHasher};
+pub use poseidon::{poseidon_hash, poseidon_hash_many, poseidon_hash_single, PoseidonHasher};
+pub use rfc6979::generate_k;
+pub use signature::{recover, recover_public_key, Signature};
+
 #[derive(Clone, Copy)]
 pub struct PublicKey(pub(crate) sys::crypto::stark::PublicKey);
 
@@ -28,6 +48,17 @@ impl PublicKey {
     pub fn curve(&self) -> Curve {
         Curve::Stark256
     }
+
+    /// Returns the x coordinate of the (uncompressed) key,
+    /// which is what Starknet uses as public key
+    pub fn x(&self) -> &[u8; 32] {
+        arrayref::array_ref![self.as_ref(), 1, 32]
+    }
+
+    /// Returns the key as a point of the curve, to verify signatures with
+    pub fn point(&self) -> Option<AffinePoint> {
+        ecdsa::public_key_point(self.as_ref())
+    }
 }
 
 impl AsRef<[u8]> for PublicKey {
@@ -84,16 +115,52 @@ impl TryFrom<sys::crypto::Curve> for Curve {
     }
 }
 
-pub struct SecretKey<const B: usize>(sys::crypto::stark::SecretKey<B>);
+mod impls {
+    cfg_if::cfg_if! {
+        if #[cfg(any(unix, windows))] {
+            use bolos::hash::{Hasher, Sha256};
+
+            use crate::utils::ApduPanic;
+
+            /// Provide a mock for tests:
+            /// the key is the SHA-256 of the path components, cut below 2^251
+            ///
+            /// # Safety
+            ///
+            /// `path` must point to `path_len` components and `out` to 32 writable bytes
+            pub unsafe fn stark_derive_private_key(path: *const u32, path_len: u8, out: *mut u8) -> u8 {
+                let path = std::slice::from_raw_parts(path, path_len as usize);
+
+                let mut hasher = Sha256::new().apdu_unwrap();
+                for component in path {
+                    hasher.update(&component.to_be_bytes()).apdu_unwrap();
+                }
+                let mut key = hasher.finalize().apdu_unwrap();
+                key[0] &= 0x07;
+
+                out.copy_from_nonoverlapping(key.as_ptr(), key.len());
+
+                0
+            }
+        } else {
+            extern "C" {
+                ///Link to the C code
+                pub fn stark_derive_private_key(path: *const u32, path_len: u8, out: *mut u8) -> u8;
+            }
+        }
+    }
+}
+
+pub struct SecretKey<const B: usize>(sys::crypto::stark::SecretKey<B>, BIP32Path<B>);
 
 pub enum SignError {
-    BufferTooSmall,
-    Sys(Error),
+    /// The private key couldn't be derived
+    Derivation,
 }
 
 impl<const B: usize> SecretKey<B> {
     pub fn new(path: BIP32Path<B>) -> Self {
-        Self(sys::crypto::stark::SecretKey::new(path))
+        Self(sys::crypto::stark::SecretKey::new(path), path)
     }
 
     pub fn into_public(self) -> Result<PublicKey, Error> {
@@ -113,14 +180,47 @@ impl<const B: usize> SecretKey<B> {
         Curve::Stark256
     }
 
-    pub fn sign(&self, data: &[u8], out: &mut [u8]) -> Result<(bool, usize), SignError> {
-        if out.len() < STARK_SIGN_BUFFER_MIN_LENGTH {
-            return Err(SignError::BufferTooSmall);
+    /// Derives the private key, as the SDK does for the keys it holds
+    ///
+    /// The key must never leave the app
+    #[inline(never)]
+    pub(crate) fn private_key(&self) -> Result<Scalar, SignError> {
+        let components = self.1.components();
+        let mut raw = [0; 32];
+
+        //safe: `components` and `raw` are valid for the lengths given
+        let err = unsafe {
+            impls::stark_derive_private_key(
+                components.as_ptr(),
+                components.len() as u8,
+                raw.as_mut_ptr(),
+            )
+        };
+        let key = Scalar::from_be_bytes(&raw).filter(|key| !key.is_zero());
+
+        //safe: `raw` is a valid local
+        unsafe { core::ptr::write_volatile(&mut raw, [0; 32]) };
+
+        match (err, key) {
+            (0, Some(key)) => Ok(key),
+            _ => Err(SignError::Derivation),
         }
+    }
+
+    /// Signs `hash` with an RFC 6979 nonce, like the Starknet tooling does,
+    /// so the same key and hash always give the same signature
+    ///
+    /// `seed` is optional extra entropy mixed in the nonce. The whole 32 bytes
+    /// of `hash` are signed, see [`sign_reduced`]
+    #[inline(never)]
+    pub fn sign(&self, hash: &[u8; 32], seed: Option<&[u8; 32]>) -> Result<Signature, SignError> {
+        let mut key = self.private_key()?;
+        let signature = sign_reduced(&key, hash, seed);
+
+        //safe: `key` is a valid local
+        unsafe { core::ptr::write_volatile(&mut key, Scalar::ZERO) };
 
-        self.0
-            .sign(data, out) //pass Sha256 for the signature nonce hasher
-            .map_err(SignError::Sys)
+        Ok(signature)
     }
 }
 
@@ -0,0 +1,276 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Arithmetic over the Stark curve, y^2 = x^3 + alpha * x + beta, with alpha = 1
+
+use super::felt::{Element, Felt, Modulus, U256};
+
+/// Order of the curve generator
+pub const CURVE_ORDER: U256 = U256([
+    0x1e66a241adc64d2f,
+    0xb781126dcae7b232,
+    0xffffffffffffffff,
+    0x0800000000000010,
+]);
+
+/// Parameters of the scalar field, the integers modulo the curve order
+pub struct CurveOrder;
+
+impl Modulus for CurveOrder {
+    const MODULUS: U256 = CURVE_ORDER;
+    const R: U256 = U256([
+        0x51925a0bf4fca74f,
+        0xc75ec4b46df16bee,
+        0x0000000000000008,
+        0x07fffffffffffdf1,
+    ]);
+    const R2: U256 = U256([
+        0x6021b3f1ea1c688d,
+        0x509cf64d14ce60b9,
+        0xbaf0ab4cf78bbabb,
+        0x07d9e57c2333766e,
+    ]);
+    const INV: u64 = 0xbb6b3c4ce8bde631;
+}
+
+/// A scalar of the Stark curve
+pub type Scalar = Element<CurveOrder>;
+
+/// Generator of the curve
+pub const GENERATOR: AffinePoint = AffinePoint::from_montgomery(
+    [
+        0xc9019623cf0273dd,
+        0x51a9bf65d4403dea,
+        0x0429bf5184041c7b,
+        0x033840300bf6cec1,
+    ],
+    [
+        0x569d0da34235308a,
+        0x0939e3442869bbe7,
+        0xfbd89a97cf4b33ad,
+        0x05a0e71610f55329,
+    ],
+);
+
+/// Point on the curve, in affine coordinates
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct AffinePoint {
+    pub x: Felt,
+    pub y: Felt,
+}
+
+/// Point on the curve, in jacobian coordinates
+///
+/// (X, Y, Z) represents the affine point (X / Z^2, Y / Z^3), Z = 0 is the point at infinity
+#[derive(Clone, Copy)]
+pub struct ProjectivePoint {
+    x: Felt,
+    y: Felt,
+    z: Felt,
+}
+
+impl AffinePoint {
+    pub(crate) const fn from_montgomery(x: [u64; 4], y: [u64; 4]) -> Self {
+        Self {
+            x: Felt::from_montgomery(x),
+            y: Felt::from_montgomery(y),
+        }
+    }
+
+    /// Computes x^3 + alpha * x + beta, the right hand side of the curve equation
+    fn y_squared(x: &Felt) -> Felt {
+        let beta = Felt::from_montgomery([
+            0x359ddd67b59a21ca,
+            0x6725f2237aab9006,
+            0xab8a1e002a41f947,
+            0x013931651774247f,
+        ]);
+
+        x.square().mul(x).add(x).add(&beta)
+    }
+
+    /// Checks that the point satisfies the curve equation
+    pub fn is_on_curve(&self) -> bool {
+        self.y.square() == Self::y_squared(&self.x)
+    }
+
+    /// Finds the point with the given x coordinate and parity of y
+    ///
+    /// Returns `None` if there's no point with this x coordinate
+    pub fn from_x(x: Felt, odd_y: bool) -> Option<Self> {
+        let y = Self::y_squared(&x).sqrt()?;
+        let y = if y.to_u256().bit(0) == odd_y {
+            y
+        } else {
+            y.neg()
+        };
+
+        Some(Self { x, y })
+    }
+
+    pub fn neg(&self) -> Self {
+        Self {
+            x: self.x,
+            y: self.y.neg(),
+        }
+    }
+}
+
+impl From<&AffinePoint> for ProjectivePoint {
+    fn from(p: &AffinePoint) -> Self {
+        Self {
+            x: p.x,
+            y: p.y,
+            z: Felt::ONE,
+        }
+    }
+}
+
+impl ProjectivePoint {
+    pub const IDENTITY: Self = Self {
+        x: Felt::ONE,
+        y: Felt::ONE,
+        z: Felt::ZERO,
+    };
+
+    pub fn is_identity(&self) -> bool {
+        self.z.is_zero()
+    }
+
+    /// Returns `None` for the point at infinity
+    pub fn to_affine(self) -> Option<AffinePoint> {
+        let zinv = self.z.inverse()?;
+        let zinv2 = zinv.square();
+
+        Some(AffinePoint {
+            x: self.x.mul(&zinv2),
+            y: self.y.mul(&zinv2).mul(&zinv),
+        })
+    }
+
+    #[inline(never)]
+    pub fn double(&self) -> Self {
+        if self.is_identity() || self.y.is_zero() {
+            return Self::IDENTITY;
+        }
+
+        //dbl-2007-bl, with a = 1
+        let xx = self.x.square();
+        let yy = self.y.square();
+        let yyyy = yy.square();
+        let zz = self.z.square();
+
+        let s = self.x.add(&yy).square().sub(&xx).sub(&yyyy).double();
+        let m = xx.double().add(&xx).add(&zz.square());
+        let t = m.square().sub(&s.double());
+
+        let y = m.mul(&s.sub(&t)).sub(&yyyy.double().double().double());
+        let z = self.y.add(&self.z).square().sub(&yy).sub(&zz);
+
+        Self { x: t, y, z }
+    }
+
+    /// Adds an affine point to this one
+    #[inline(never)]
+    pub fn add_affine(&self, other: &AffinePoint) -> Self {
+        if self.is_identity() {
+            return other.into();
+        }
+
+        //madd-2007-bl
+        let z1z1 = self.z.square();
+        let u2 = other.x.mul(&z1z1);
+        let s2 = other.y.mul(&self.z).mul(&z1z1);
+
+        let h = u2.sub(&self.x);
+        let r = s2.sub(&self.y).double();
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::IDENTITY
+            };
+        }
+
+        let hh = h.square();
+        let i = hh.double().double();
+        let j = h.mul(&i);
+        let v = self.x.mul(&i);
+
+        let x = r.square().sub(&j).sub(&v.double());
+        let y = r.mul(&v.sub(&x)).sub(&self.y.mul(&j).double());
+        let z = self.z.add(&h).square().sub(&z1z1).sub(&hh);
+
+        Self { x, y, z }
+    }
+
+    #[inline(never)]
+    pub fn add(&self, other: &Self) -> Self {
+        if self.is_identity() {
+            return *other;
+        }
+        if other.is_identity() {
+            return *self;
+        }
+
+        //add-2007-bl
+        let z1z1 = self.z.square();
+        let z2z2 = other.z.square();
+        let u1 = self.x.mul(&z2z2);
+        let u2 = other.x.mul(&z1z1);
+        let s1 = self.y.mul(&other.z).mul(&z2z2);
+        let s2 = other.y.mul(&self.z).mul(&z1z1);
+
+        let h = u2.sub(&u1);
+        let r = s2.sub(&s1).double();
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double()
+            } else {
+                Self::IDENTITY
+            };
+        }
+
+        let i = h.double().square();
+        let j = h.mul(&i);
+        let v = u1.mul(&i);
+
+        let x = r.square().sub(&j).sub(&v.double());
+        let y = r.mul(&v.sub(&x)).sub(&s1.mul(&j).double());
+        let z = self.z.add(&other.z).square().sub(&z1z1).sub(&z2z2).mul(&h);
+
+        Self { x, y, z }
+    }
+}
+
+/// Computes `scalar * point`, only considering the lowest `bits` bits of `scalar`
+#[inline(never)]
+pub fn mul_bits(point: &AffinePoint, scalar: &U256, bits: usize) -> ProjectivePoint {
+    let mut out = ProjectivePoint::IDENTITY;
+    for i in (0..bits).rev() {
+        out = out.double();
+        if scalar.bit(i) {
+            out = out.add_affine(point);
+        }
+    }
+
+    out
+}
+
+/// Computes `scalar * point`
+pub fn mul(point: &AffinePoint, scalar: &U256) -> ProjectivePoint {
+    mul_bits(point, scalar, scalar.bits())
+}
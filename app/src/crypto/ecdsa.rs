@@ -0,0 +1,339 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! ECDSA over the Stark curve
+
+use bolos::PIC;
+
+use super::{
+    curve::{mul, AffinePoint, Scalar, GENERATOR},
+    felt::{Felt, U256},
+    rfc6979::generate_k,
+    signature::Signature,
+};
+
+/// Converts the signed hash to a scalar
+///
+/// The whole 32-byte hash is reduced modulo the order: the SDK signer only
+/// truncates hashes longer than the byte length of the curve domain (32 bytes
+/// for Stark256), it doesn't drop the bits above the bit length of the order.
+/// For felts this is the hash as Starknet signs it, and 256-bit digests
+/// (as signed by [`Sign`](crate::handlers::signing::Sign)) verify the same way
+pub(crate) fn message_scalar(hash: &[u8; 32]) -> Scalar {
+    Scalar::from_u256_reduced(U256::from_be_bytes(hash))
+}
+
+/// Reads an uncompressed (0x04 || x || y) public key
+pub fn public_key_point(public_key: &[u8]) -> Option<AffinePoint> {
+    if public_key.len() != 65 || public_key[0] != 0x04 {
+        return None;
+    }
+
+    let x = Felt::from_be_bytes(arrayref::array_ref![public_key, 1, 32])?;
+    let y = Felt::from_be_bytes(arrayref::array_ref![public_key, 33, 32])?;
+    let point = AffinePoint { x, y };
+
+    if point.is_on_curve() {
+        Some(point)
+    } else {
+        None
+    }
+}
+
+/// Returns the public key of `private_key`
+pub fn public_key(private_key: &Scalar) -> Option<AffinePoint> {
+    let generator = PIC::new(&GENERATOR).into_inner();
+    mul(generator, &private_key.to_u256()).to_affine()
+}
+
+/// 2^251, both the hash and the signature components must be smaller
+const ELEMENT_UPPER_BOUND: U256 = U256([0, 0, 0, 0x0800000000000000]);
+
+pub(crate) fn in_element_range(n: &U256) -> bool {
+    !n.is_zero() && *n < ELEMENT_UPPER_BOUND
+}
+
+/// Signs `hash` with `private_key` using the nonce `k`
+///
+/// Returns `None` if the nonce doesn't give a valid signature
+pub fn sign_with_k(private_key: &Scalar, hash: &[u8; 32], k: &U256) -> Option<Signature> {
+    let k = Scalar::from_u256(*k).filter(|k| !k.is_zero())?;
+
+    let generator = PIC::new(&GENERATOR).into_inner();
+    let point = mul(generator, &k.to_u256()).to_affine()?;
+
+    let r = point.x.to_u256();
+    if !in_element_range(&r) {
+        return None;
+    }
+
+    //r is smaller than 2^251 and so than the order
+    let r_scalar = Scalar::from_u256_reduced(r);
+    let s = message_scalar(hash)
+        .add(&r_scalar.mul(private_key))
+        .mul(&k.inverse()?)
+        .to_u256();
+    if !in_element_range(&s) {
+        return None;
+    }
+
+    Some(Signature {
+        r: r.to_be_bytes(),
+        s: s.to_be_bytes(),
+        v: point.y.to_u256().bit(0) as u8,
+    })
+}
+
+/// Signs `hash` with `private_key`, with an RFC 6979 nonce
+///
+/// `seed` is optional extra entropy; when a nonce is unusable the seed is
+/// incremented and a new one is generated, like the Starknet tooling does.
+/// S is left as computed, [`Signature::normalize`] gives the form returned by the app.
+///
+/// Returns `None` if the hash is not smaller than 2^251
+#[inline(never)]
+pub fn sign(private_key: &Scalar, hash: &[u8; 32], seed: Option<&[u8; 32]>) -> Option<Signature> {
+    if U256::from_be_bytes(hash) >= ELEMENT_UPPER_BOUND {
+        return None;
+    }
+
+    Some(sign_reduced(private_key, hash, seed))
+}
+
+/// Signs `hash` like [`sign`], but accepts any 32-byte hash
+///
+/// The hash is reduced modulo the order, as the SDK signer does for
+/// 256-bit digests; below 2^251 the signature is the same as [`sign`]
+#[inline(never)]
+pub fn sign_reduced(private_key: &Scalar, hash: &[u8; 32], seed: Option<&[u8; 32]>) -> Signature {
+    let key_bytes = private_key.to_be_bytes();
+    let mut seed = seed.map(U256::from_be_bytes);
+    loop {
+        let seed_bytes = seed.map(|seed| seed.to_be_bytes());
+        let k = generate_k(hash, &key_bytes, seed_bytes.as_ref());
+        if let Some(signature) = sign_with_k(private_key, hash, &k) {
+            return signature;
+        }
+
+        seed = Some(match seed {
+            Some(seed) => seed.overflowing_add(&U256::ONE).0,
+            None => U256::ONE,
+        });
+    }
+}
+
+/// Verifies the signature (`r`, `s`) of `hash` made by `public_key`
+#[inline(never)]
+pub fn verify(public_key: &AffinePoint, hash: &[u8; 32], r: &[u8; 32], s: &[u8; 32]) -> bool {
+    let r = match Scalar::from_u256(U256::from_be_bytes(r)) {
+        Some(r) if !r.is_zero() => r,
+        _ => return false,
+    };
+    //zero has no inverse
+    let w = match Scalar::from_u256(U256::from_be_bytes(s)).and_then(|s| s.inverse()) {
+        Some(w) => w,
+        None => return false,
+    };
+
+    let u1 = message_scalar(hash).mul(&w);
+    let u2 = r.mul(&w);
+
+    let generator = PIC::new(&GENERATOR).into_inner();
+    let point = mul(generator, &u1.to_u256()).add(&mul(public_key, &u2.to_u256()));
+
+    match point.to_affine() {
+        Some(point) => Scalar::from_u256_reduced(point.x.to_u256()) == r,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::curve::CURVE_ORDER;
+
+    fn bytes(s: &str) -> [u8; 32] {
+        let mut out = [0; 32];
+        out.copy_from_slice(&hex::decode(s).unwrap());
+        out
+    }
+
+    fn public_key() -> AffinePoint {
+        let mut key = std::vec![0x04];
+        key.extend_from_slice(&bytes(
+            "077a3b314db07c45076d11f62b6f9e748a39790441823307743cf00d6597ea43",
+        ));
+        key.extend_from_slice(&bytes(
+            "054d7beec5ec728223671c627557efc5c9a6508425dc6c900b7741bf60afec06",
+        ));
+
+        public_key_point(&key).unwrap()
+    }
+
+    const HASH: &str = "0397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f";
+    const R: &str = "04838c4ed86f1c9b4fe1aac884ac79431b0243b79d9e24500eb0f6229f4fe72a";
+    const S: &str = "004a0466a5c33405ce93c2f85b0d324ad7403fa4266241323ed39f9df1043770";
+
+    #[test]
+    fn generator() {
+        assert!(GENERATOR.is_on_curve());
+        assert!(mul(&GENERATOR, &CURVE_ORDER).is_identity());
+    }
+
+    #[test]
+    fn valid_signature() {
+        let key = public_key();
+        assert!(verify(&key, &bytes(HASH), &bytes(R), &bytes(S)));
+
+        //the top 4 bits of the hash are signed too
+        let mut hash = bytes(HASH);
+        hash[0] |= 0xF0;
+        assert!(!verify(&key, &hash, &bytes(R), &bytes(S)));
+    }
+
+    #[test]
+    fn wide_hashes() {
+        let key = private_key("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let generator = PIC::new(&GENERATOR).into_inner();
+        let public_key = mul(generator, &key.to_u256()).to_affine().unwrap();
+
+        //SHA-256 of "hello@zondax.ch", as signed by `Sign`
+        let hash = bytes("172c405e766372a79c21b3f7795d5265a9ee1a67e216049c17bbeb8ae2450f8b");
+        let k = U256::from_be_bytes(&bytes(
+            "050a50e20a9fb5b33f618ce4ddec8df60f40d3ac3018453bcc002cee71140cd4",
+        ));
+        let signature = sign_with_k(&key, &hash, &k).unwrap();
+        assert!(verify(&public_key, &hash, &signature.r, &signature.s));
+
+        //neither the low nor the high 252 bits of the hash are what is signed
+        let mut low = hash;
+        low[0] &= 0x0F;
+        assert!(!verify(&public_key, &low, &signature.r, &signature.s));
+        let high = U256::from_be_bytes(&hash).shr_small(4).to_be_bytes();
+        assert!(!verify(&public_key, &high, &signature.r, &signature.s));
+    }
+
+    #[test]
+    fn invalid_signatures() {
+        let key = public_key();
+
+        let mut s = bytes(S);
+        s[31] ^= 1;
+        assert!(!verify(&key, &bytes(HASH), &bytes(R), &s));
+
+        let mut hash = bytes(HASH);
+        hash[31] ^= 1;
+        assert!(!verify(&key, &hash, &bytes(R), &bytes(S)));
+
+        assert!(!verify(&key.neg(), &bytes(HASH), &bytes(R), &bytes(S)));
+        assert!(!verify(&key, &bytes(HASH), &[0; 32], &bytes(S)));
+        assert!(!verify(
+            &key,
+            &bytes(HASH),
+            &bytes(R),
+            &CURVE_ORDER.to_be_bytes()
+        ));
+    }
+
+    fn private_key(s: &str) -> Scalar {
+        Scalar::from_be_bytes(&bytes(s)).unwrap()
+    }
+
+    #[test]
+    fn starknet_crypto_signatures() {
+        //private key, hash, r, s, v
+        let vectors = [
+            (
+                "03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc",
+                "0397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f",
+                "0173fd03d8b008ee7432977ac27d1e9d1a1f6c98b1a2f05fa84a21c84c44e882",
+                "04b6d75385aed025aa222f28a0adc6d58db78ff17e51c3f59e259b131cd5a1cc",
+                1,
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "00edf3922fdf0c1b98a861a38874120a437e33c08841923317aeb8ec6bad1400",
+                "00a658327ad247b8e816aadd7758d96450f8d43c691aadf768cadd8784f3b8ef",
+                0,
+            ),
+            (
+                "07e0d6f3eb6fb54a3ac0f2bbe9e4a5b50a4a1fb9e5d0b3e4b6b6f6e4a3c2d1e0",
+                "02d4f1a9c7b5e3e1f0a8c6b4d2e0f1a3c5b7d9e1f3a5c7e9b1d3f5a7c9e1b3d5",
+                "05b055ac1ca8bdebf3f06a50e8a7e6670bf231747ec214b8ed046823203a6e2b",
+                "0764f10ee1c9387bfbdeb369cd6335988f318240e66e2368c98b0727538b3a90",
+                1,
+            ),
+        ];
+
+        for &(key, hash, r, s, v) in vectors.iter() {
+            let signature = sign(&private_key(key), &bytes(hash), None).unwrap();
+            assert_eq!(hex::encode(signature.r), r);
+            assert_eq!(hex::encode(signature.s), s);
+            assert_eq!(signature.v, v);
+        }
+    }
+
+    #[test]
+    fn signs_with_seed() {
+        let key = private_key("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let mut seed = [0; 32];
+        seed[31] = 42;
+
+        let signature = sign(&key, &bytes(HASH), Some(&seed)).unwrap();
+        assert_eq!(
+            hex::encode(signature.r),
+            "03b2d2ef60d3459ea51d37fc16b2d8c2f583bc32b208d57f70bc9c7ebe0567d7"
+        );
+        assert_eq!(
+            hex::encode(signature.s),
+            "03fd9f9cd089b8f8c0c340668059feb3fcdf831f899a4464af29d48e1ce3633b"
+        );
+
+        //and it verifies
+        assert!(verify(
+            &public_key(),
+            &bytes(HASH),
+            &signature.r,
+            &signature.s
+        ));
+    }
+
+    #[test]
+    fn refuses_wide_hashes() {
+        let key = private_key("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let mut hash = [0; 32];
+        hash[0] = 0x08;
+
+        assert!(sign(&key, &hash, None).is_none());
+
+        //unless reduced, as the device signs them
+        let signature = sign_reduced(&key, &hash, None);
+        let generator = PIC::new(&GENERATOR).into_inner();
+        let public_key = mul(generator, &key.to_u256()).to_affine().unwrap();
+        assert!(verify(&public_key, &hash, &signature.r, &signature.s));
+    }
+
+    #[test]
+    fn invalid_public_keys() {
+        assert!(public_key_point(&[0x04; 64]).is_none());
+
+        //not on the curve
+        let mut key = [0; 65];
+        key[0] = 0x04;
+        key[64] = 1;
+        assert!(public_key_point(&key).is_none());
+    }
+}
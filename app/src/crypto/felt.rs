@@ -0,0 +1,518 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use core::{cmp::Ordering, marker::PhantomData};
+
+/// 256-bit unsigned integer, stored as little-endian 64-bit limbs
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(test, derive(Debug))]
+pub struct U256(pub(crate) [u64; 4]);
+
+#[inline(always)]
+const fn adc(a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = a as u128 + b as u128 + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+#[inline(always)]
+const fn sbb(a: u64, b: u64, borrow: u64) -> (u64, u64) {
+    let t = (a as u128).wrapping_sub(b as u128 + borrow as u128);
+    (t as u64, ((t >> 64) as u64) & 1)
+}
+
+#[inline(always)]
+const fn mac(acc: u64, a: u64, b: u64, carry: u64) -> (u64, u64) {
+    let t = acc as u128 + (a as u128 * b as u128) + carry as u128;
+    (t as u64, (t >> 64) as u64)
+}
+
+impl U256 {
+    pub const ZERO: Self = Self([0; 4]);
+    pub const ONE: Self = Self([1, 0, 0, 0]);
+
+    pub const fn from_u64(n: u64) -> Self {
+        Self([n, 0, 0, 0])
+    }
+
+    pub const fn from_u128(n: u128) -> Self {
+        Self([n as u64, (n >> 64) as u64, 0, 0])
+    }
+
+    /// Construct from the two 128-bit halves, as used by Cairo's `u256`
+    pub const fn from_low_high(low: u128, high: u128) -> Self {
+        Self([
+            low as u64,
+            (low >> 64) as u64,
+            high as u64,
+            (high >> 64) as u64,
+        ])
+    }
+
+    pub fn from_be_bytes(bytes: &[u8; 32]) -> Self {
+        let mut limbs = [0; 4];
+        for (limb, chunk) in limbs.iter_mut().rev().zip(bytes.chunks_exact(8)) {
+            let mut array = [0; 8];
+            array.copy_from_slice(chunk);
+            *limb = u64::from_be_bytes(array);
+        }
+
+        Self(limbs)
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        let mut out = [0; 32];
+        for (limb, chunk) in self.0.iter().rev().zip(out.chunks_exact_mut(8)) {
+            chunk.copy_from_slice(&limb.to_be_bytes());
+        }
+
+        out
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0; 4]
+    }
+
+    /// Returns the value of the `n`th bit, starting from the least significant
+    pub fn bit(&self, n: usize) -> bool {
+        (self.0[n / 64] >> (n % 64)) & 1 == 1
+    }
+
+    /// Returns the minimum number of bits necessary to represent this number
+    pub fn bits(&self) -> usize {
+        for (i, limb) in self.0.iter().enumerate().rev() {
+            if *limb != 0 {
+                return i * 64 + 64 - limb.leading_zeros() as usize;
+            }
+        }
+
+        0
+    }
+
+    pub fn overflowing_add(&self, other: &Self) -> (Self, bool) {
+        let mut out = [0; 4];
+        let mut carry = 0;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (v, c) = adc(self.0[i], other.0[i], carry);
+            *limb = v;
+            carry = c;
+        }
+
+        (Self(out), carry != 0)
+    }
+
+    pub fn overflowing_sub(&self, other: &Self) -> (Self, bool) {
+        let mut out = [0; 4];
+        let mut borrow = 0;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (v, b) = sbb(self.0[i], other.0[i], borrow);
+            *limb = v;
+            borrow = b;
+        }
+
+        (Self(out), borrow != 0)
+    }
+
+    /// Shift right by `n` bits, with `n` < 64
+    pub fn shr_small(&self, n: u32) -> Self {
+        if n == 0 {
+            return *self;
+        }
+
+        let mut out = [0; 4];
+        for (i, limb) in out.iter_mut().enumerate() {
+            let high = self.0.get(i + 1).map(|h| h << (64 - n)).unwrap_or_default();
+            *limb = (self.0[i] >> n) | high;
+        }
+
+        Self(out)
+    }
+
+    /// Multiply by a small factor, returning the overflowing limb
+    pub fn overflowing_mul_small(&self, factor: u64) -> (Self, u64) {
+        let mut out = [0; 4];
+        let mut carry = 0;
+        for (i, limb) in out.iter_mut().enumerate() {
+            let (v, c) = mac(0, self.0[i], factor, carry);
+            *limb = v;
+            carry = c;
+        }
+
+        (Self(out), carry)
+    }
+
+    /// Divide by a small divisor in place, returning the remainder
+    pub fn div_rem_small(&mut self, divisor: u64) -> u64 {
+        let mut rem = 0u128;
+        for limb in self.0.iter_mut().rev() {
+            let cur = (rem << 64) | *limb as u128;
+            *limb = (cur / divisor as u128) as u64;
+            rem = cur % divisor as u128;
+        }
+
+        rem as u64
+    }
+}
+
+impl PartialOrd for U256 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for U256 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for (a, b) in self.0.iter().rev().zip(other.0.iter().rev()) {
+            match a.cmp(b) {
+                Ordering::Equal => continue,
+                ord => return ord,
+            }
+        }
+
+        Ordering::Equal
+    }
+}
+
+/// Describes a prime modulus (smaller than 2^255) used for montgomery arithmetic
+pub trait Modulus {
+    /// The modulus itself
+    const MODULUS: U256;
+    /// 2^256 mod MODULUS
+    const R: U256;
+    /// 2^512 mod MODULUS
+    const R2: U256;
+    /// -MODULUS^-1 mod 2^64
+    const INV: u64;
+}
+
+/// Element of the prime field defined by `M`, kept in montgomery form
+pub struct Element<M>(U256, PhantomData<M>);
+
+impl<M> Clone for Element<M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for Element<M> {}
+
+impl<M> PartialEq for Element<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<M> Eq for Element<M> {}
+
+#[cfg(test)]
+impl<M: Modulus> std::fmt::Debug for Element<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x")?;
+        for b in self.to_be_bytes().iter() {
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl<M: Modulus> Element<M> {
+    pub const ZERO: Self = Self(U256::ZERO, PhantomData);
+    pub const ONE: Self = Self(M::R, PhantomData);
+
+    /// Construct an element from its montgomery representation
+    pub(crate) const fn from_montgomery(limbs: [u64; 4]) -> Self {
+        Self(U256(limbs), PhantomData)
+    }
+
+    /// Construct an element from an integer already smaller than the modulus
+    fn from_canonical(n: U256) -> Self {
+        Self(n, PhantomData).mont_mul(&Self(M::R2, PhantomData))
+    }
+
+    pub fn from_u64(n: u64) -> Self {
+        Self::from_canonical(U256::from_u64(n))
+    }
+
+    pub fn from_u128(n: u128) -> Self {
+        Self::from_canonical(U256::from_u128(n))
+    }
+
+    /// Returns `None` if the number is not smaller than the modulus
+    pub fn from_u256(n: U256) -> Option<Self> {
+        if n < M::MODULUS {
+            Some(Self::from_canonical(n))
+        } else {
+            None
+        }
+    }
+
+    /// Reduce the given integer modulo the field modulus
+    pub fn from_u256_reduced(mut n: U256) -> Self {
+        while n >= M::MODULUS {
+            n = n.overflowing_sub(&M::MODULUS).0;
+        }
+
+        Self::from_canonical(n)
+    }
+
+    /// Reads a big-endian encoded element
+    ///
+    /// Returns `None` if the number is not smaller than the modulus
+    pub fn from_be_bytes(bytes: &[u8; 32]) -> Option<Self> {
+        Self::from_u256(U256::from_be_bytes(bytes))
+    }
+
+    pub fn to_u256(self) -> U256 {
+        self.mont_mul(&Self(U256::ONE, PhantomData)).0
+    }
+
+    pub fn to_be_bytes(self) -> [u8; 32] {
+        self.to_u256().to_be_bytes()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    #[inline(never)]
+    fn mont_mul(&self, other: &Self) -> Self {
+        let (a, b, m) = (&self.0 .0, &other.0 .0, &M::MODULUS.0);
+
+        let mut t = [0u64; 6];
+        for b_i in b.iter() {
+            let mut carry = 0;
+            for j in 0..4 {
+                let (v, c) = mac(t[j], a[j], *b_i, carry);
+                t[j] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[4], carry, 0);
+            t[4] = v;
+            t[5] = c;
+
+            let k = t[0].wrapping_mul(M::INV);
+            let (_, mut carry) = mac(t[0], k, m[0], 0);
+            for j in 1..4 {
+                let (v, c) = mac(t[j], k, m[j], carry);
+                t[j - 1] = v;
+                carry = c;
+            }
+            let (v, c) = adc(t[4], carry, 0);
+            t[3] = v;
+            t[4] = t[5] + c;
+        }
+
+        let out = U256([t[0], t[1], t[2], t[3]]);
+        if t[4] != 0 || out >= M::MODULUS {
+            Self(out.overflowing_sub(&M::MODULUS).0, PhantomData)
+        } else {
+            Self(out, PhantomData)
+        }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        //both are smaller than the modulus which is smaller than 2^255, so no overflow
+        let (sum, _) = self.0.overflowing_add(&other.0);
+        if sum >= M::MODULUS {
+            Self(sum.overflowing_sub(&M::MODULUS).0, PhantomData)
+        } else {
+            Self(sum, PhantomData)
+        }
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        let (diff, borrow) = self.0.overflowing_sub(&other.0);
+        if borrow {
+            Self(diff.overflowing_add(&M::MODULUS).0, PhantomData)
+        } else {
+            Self(diff, PhantomData)
+        }
+    }
+
+    pub fn neg(&self) -> Self {
+        Self::ZERO.sub(self)
+    }
+
+    pub fn double(&self) -> Self {
+        self.add(self)
+    }
+
+    pub fn mul(&self, other: &Self) -> Self {
+        self.mont_mul(other)
+    }
+
+    pub fn square(&self) -> Self {
+        self.mont_mul(self)
+    }
+
+    /// Raise to the power of the given exponent
+    pub fn pow(&self, exp: &U256) -> Self {
+        let mut out = Self::ONE;
+        for i in (0..exp.bits()).rev() {
+            out = out.square();
+            if exp.bit(i) {
+                out = out.mul(self);
+            }
+        }
+
+        out
+    }
+
+    /// Computes the multiplicative inverse, via Fermat's little theorem
+    ///
+    /// Returns `None` for zero
+    pub fn inverse(&self) -> Option<Self> {
+        if self.is_zero() {
+            return None;
+        }
+
+        let (exp, _) = M::MODULUS.overflowing_sub(&U256::from_u64(2));
+        Some(self.pow(&exp))
+    }
+}
+
+/// Parameters of the Stark field, p = 2^251 + 17 * 2^192 + 1
+pub struct StarkField;
+
+impl Modulus for StarkField {
+    const MODULUS: U256 = U256([
+        0x0000000000000001,
+        0x0000000000000000,
+        0x0000000000000000,
+        0x0800000000000011,
+    ]);
+    const R: U256 = U256([
+        0xffffffffffffffe1,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x07fffffffffffdf0,
+    ]);
+    const R2: U256 = U256([
+        0xfffffd737e000401,
+        0x00000001330fffff,
+        0xffffffffff6f8000,
+        0x07ffd4ab5e008810,
+    ]);
+    const INV: u64 = 0xffffffffffffffff;
+}
+
+/// A Starknet field element
+pub type Felt = Element<StarkField>;
+
+impl Element<StarkField> {
+    /// Computes a square root, with Tonelli-Shanks as p - 1 = 2^192 * (2^59 + 17)
+    ///
+    /// Returns `None` if the element is not a square
+    #[inline(never)]
+    pub fn sqrt(&self) -> Option<Self> {
+        const TWO_ADICITY: usize = 192;
+        //odd part of p - 1
+        const Q: U256 = U256::from_u64((1 << 59) + 17);
+        //(Q + 1) / 2
+        const Q_HALF: U256 = U256::from_u64((1 << 58) + 9);
+
+        if self.is_zero() {
+            return Some(*self);
+        }
+
+        //3 generates the multiplicative group, so it's not a square
+        let mut c = Self::from_u64(3).pow(&Q);
+        let mut x = self.pow(&Q_HALF);
+        let mut t = self.pow(&Q);
+        let mut m = TWO_ADICITY;
+
+        while t != Self::ONE {
+            //least i such that t^(2^i) = 1
+            let mut i = 0;
+            let mut t2i = t;
+            while t2i != Self::ONE {
+                t2i = t2i.square();
+                i += 1;
+                if i == m {
+                    return None;
+                }
+            }
+
+            let mut b = c;
+            for _ in 0..m - i - 1 {
+                b = b.square();
+            }
+
+            x = x.mul(&b);
+            c = b.square();
+            t = t.mul(&c);
+            m = i;
+        }
+
+        Some(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felt(s: &str) -> Felt {
+        let mut bytes = [0; 32];
+        let decoded = hex::decode(std::format!("{:0>64}", s)).unwrap();
+        bytes.copy_from_slice(&decoded);
+        Felt::from_be_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn roundtrip() {
+        let x = felt("3d937c035c878245caf64531a5756109c53068da139362728feb561405371cb");
+        assert_eq!(
+            hex::encode(x.to_be_bytes()),
+            "03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb"
+        );
+        assert!(Felt::from_be_bytes(&StarkField::MODULUS.to_be_bytes()).is_none());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Felt::from_u64(7);
+        let b = Felt::from_u64(5);
+
+        assert_eq!(a.add(&b), Felt::from_u64(12));
+        assert_eq!(a.mul(&b), Felt::from_u64(35));
+        assert_eq!(b.sub(&a).add(&Felt::from_u64(2)), Felt::ZERO);
+        assert_eq!(a.inverse().unwrap().mul(&a), Felt::ONE);
+
+        //p - 1 squared is 1
+        let minus_one = Felt::ONE.neg();
+        assert_eq!(minus_one.square(), Felt::ONE);
+        assert_eq!(
+            hex::encode(minus_one.to_be_bytes()),
+            "0800000000000011000000000000000000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn square_roots() {
+        let x = felt("3d937c035c878245caf64531a5756109c53068da139362728feb561405371cb");
+        let root = x.square().sqrt().unwrap();
+        assert!(root == x || root == x.neg());
+
+        assert_eq!(Felt::ZERO.sqrt(), Some(Felt::ZERO));
+        assert_eq!(
+            Felt::from_u64(4).sqrt().unwrap().square(),
+            Felt::from_u64(4)
+        );
+
+        //3 is not a square
+        assert!(Felt::from_u64(3).sqrt().is_none());
+        assert!(Felt::from_u64(3).mul(&x.square()).sqrt().is_none());
+    }
+}
@@ -0,0 +1,144 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Stark private key derivation by "grinding", as specified by StarkWare
+//!
+//! Reducing a 256-bit hash modulo the curve order would be biased,
+//! so hashes above the largest multiple of the order are discarded
+//! and the seed is hashed again with the next index
+
+use bolos::hash::{Hasher, Sha256};
+
+use super::{curve::CURVE_ORDER, felt::U256};
+use crate::utils::ApduPanic;
+
+/// Largest multiple of the curve order that fits in 256 bits
+const GRIND_LIMIT: U256 = U256([
+    0xae6da5f40b0358b1,
+    0x38a13b4b920e9411,
+    0xfffffffffffffff7,
+    0xf80000000000020e,
+]);
+
+/// Hashes `seed` followed by the minimal big-endian encoding of `index`
+fn hash_with_index(seed: &[u8], index: u32) -> U256 {
+    let index = index.to_be_bytes();
+    //index 0 is still encoded as one byte
+    let skip = core::cmp::min(index.iter().take_while(|&&b| b == 0).count(), 3);
+
+    let mut hasher = Sha256::new().apdu_unwrap();
+    hasher.update(seed).apdu_unwrap();
+    hasher.update(&index[skip..]).apdu_unwrap();
+
+    U256::from_be_bytes(&hasher.finalize().apdu_unwrap())
+}
+
+/// Derives a Stark private key from `seed`
+///
+/// Matches `grindKey` of `@starkware-industries/starkware-crypto`
+#[inline(never)]
+pub fn grind_key(seed: &[u8]) -> U256 {
+    let mut index = 0;
+    let mut key = hash_with_index(seed, index);
+    while key >= GRIND_LIMIT {
+        index += 1;
+        key = hash_with_index(seed, index);
+    }
+
+    //smaller than 31 times the order
+    while key >= CURVE_ORDER {
+        key = key.overflowing_sub(&CURVE_ORDER).0;
+    }
+
+    key
+}
+
+/// Derives a Stark private key from an Ethereum signature (R S V)
+///
+/// Matches `getPrivateKeyFromEthSignature` of `@starkware-industries/starkware-crypto`,
+/// which grinds the R component of the signature
+pub fn private_key_from_eth_signature(signature: &[u8]) -> Option<U256> {
+    signature.get(..32).map(grind_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grind_hex(seed: &str) -> std::string::String {
+        hex::encode(grind_key(&hex::decode(seed).unwrap()).to_be_bytes())
+    }
+
+    #[test]
+    fn limit() {
+        let (order_31, carry) = CURVE_ORDER.overflowing_mul_small(31);
+        assert_eq!(carry, 0);
+        assert!(order_31 == GRIND_LIMIT);
+
+        //one more multiple doesn't fit
+        assert!(GRIND_LIMIT.overflowing_add(&CURVE_ORDER).1);
+    }
+
+    #[test]
+    fn starkware_vectors() {
+        assert_eq!(
+            grind_hex("86F3E7293141F20A8BAFF320E8EE4ACCB9D4A4BF2B4D295E8CEE784DB46E0519"),
+            "05c8c8683596c732541a59e03007b2d30dbbbb873556fe65b5fb63c16688f941"
+        );
+
+        let signature = hex::decode(
+            "21fbf0696d5e0aa2ef41a2b4ffb623bcaf070461d61cf7251c74161f82fec3a4\
+             370854bc0a34b3ab487c1bc021cd318c734c51ae29374f2beb0e6f2dd49b4bf41c",
+        )
+        .unwrap();
+        let key = private_key_from_eth_signature(&signature).unwrap();
+        assert_eq!(
+            hex::encode(key.to_be_bytes()),
+            "0766f11e90cd7c7b43085b56da35c781f8c067ac0d578eabdceebc4886435bda"
+        );
+
+        assert!(private_key_from_eth_signature(&signature[..31]).is_none());
+    }
+
+    #[test]
+    fn grinds_past_the_limit() {
+        //the first hash of these seeds is above the limit
+        assert_eq!(
+            grind_hex("a6c7ad01b1967dbab615e3f9e3eb5b83f025abe07e6ac43bd9f3b7b492c13a4f"),
+            "02e6bed1c7bc9c4c5a69384936f6a210663340ce67bc64d442f593cebe0637ca"
+        );
+        assert_eq!(
+            grind_hex("8c48d2025b54f3688c69b2c2fccc7790a15c26b0b25051fbebd31e87a31ccc7e"),
+            "03300837f2c55a3bae132bd59941a397173e8854d11b316edd528f9be8795433"
+        );
+    }
+
+    fn sha256(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).unwrap()
+    }
+
+    #[test]
+    fn index_encoding() {
+        let mut data = [7u8; 34];
+
+        //index 0 is a single zero byte
+        data[32] = 0x00;
+        assert!(hash_with_index(&data[..32], 0) == U256::from_be_bytes(&sha256(&data[..33])));
+
+        data[32] = 0x01;
+        data[33] = 0x00;
+        assert!(hash_with_index(&data[..32], 256) == U256::from_be_bytes(&sha256(&data)));
+    }
+}
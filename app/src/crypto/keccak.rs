@@ -0,0 +1,201 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Keccak-256 (the original Keccak padding, as used by Ethereum and Starknet)
+
+use bolos::PIC;
+
+use super::felt::{Felt, U256};
+
+/// Bytes absorbed per permutation
+const RATE: usize = 136;
+
+const ROUNDS: usize = 24;
+
+const ROUND_CONSTANTS: [u64; ROUNDS] = [
+    0x0000000000000001,
+    0x0000000000008082,
+    0x800000000000808a,
+    0x8000000080008000,
+    0x000000000000808b,
+    0x0000000080000001,
+    0x8000000080008081,
+    0x8000000000008009,
+    0x000000000000008a,
+    0x0000000000000088,
+    0x0000000080008009,
+    0x000000008000000a,
+    0x000000008000808b,
+    0x800000000000008b,
+    0x8000000000008089,
+    0x8000000000008003,
+    0x8000000000008002,
+    0x8000000000000080,
+    0x000000000000800a,
+    0x800000008000000a,
+    0x8000000080008081,
+    0x8000000000008080,
+    0x0000000080000001,
+    0x8000000080008008,
+];
+
+/// Rotation offsets, in the order lanes are visited by the rho and pi steps
+const RHO: [u32; 24] = [
+    1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44,
+];
+
+/// Lane visiting order of the rho and pi steps
+const PI: [usize; 24] = [
+    10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1,
+];
+
+#[inline(never)]
+fn keccak_f(state: &mut [u64; 25]) {
+    let round_constants = PIC::new(&ROUND_CONSTANTS).into_inner();
+    let rho = PIC::new(&RHO).into_inner();
+    let pi = PIC::new(&PI).into_inner();
+
+    for rc in round_constants.iter() {
+        //theta
+        let mut c = [0u64; 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            *c = state[x] ^ state[x + 5] ^ state[x + 10] ^ state[x + 15] ^ state[x + 20];
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            for y in 0..5 {
+                state[5 * y + x] ^= d;
+            }
+        }
+
+        //rho and pi
+        let mut last = state[1];
+        for (&lane, &rotation) in pi.iter().zip(rho.iter()) {
+            let tmp = state[lane];
+            state[lane] = last.rotate_left(rotation);
+            last = tmp;
+        }
+
+        //chi
+        for y in 0..5 {
+            let mut row = [0u64; 5];
+            row.copy_from_slice(&state[5 * y..5 * y + 5]);
+            for x in 0..5 {
+                state[5 * y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+            }
+        }
+
+        //iota
+        state[0] ^= rc;
+    }
+}
+
+/// Incremental Keccak-256 hasher
+#[derive(Default)]
+pub struct Keccak256 {
+    state: [u64; 25],
+    /// Number of bytes absorbed in the current block
+    offset: usize,
+}
+
+impl Keccak256 {
+    fn xor_byte(&mut self, index: usize, byte: u8) {
+        self.state[index / 8] ^= (byte as u64) << (8 * (index % 8));
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &byte in data {
+            self.xor_byte(self.offset, byte);
+            self.offset += 1;
+
+            if self.offset == RATE {
+                keccak_f(&mut self.state);
+                self.offset = 0;
+            }
+        }
+    }
+
+    pub fn finalize(mut self) -> [u8; 32] {
+        self.xor_byte(self.offset, 0x01);
+        self.xor_byte(RATE - 1, 0x80);
+        keccak_f(&mut self.state);
+
+        let mut out = [0; 32];
+        for (lane, chunk) in self.state.iter().zip(out.chunks_exact_mut(8)) {
+            chunk.copy_from_slice(&lane.to_le_bytes());
+        }
+
+        out
+    }
+}
+
+/// Computes the Keccak-256 digest of `data`
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::default();
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// Keccak-256 of `data`, truncated to its lowest 250 bits
+///
+/// This is how Starknet derives selectors and type hashes from names
+pub fn starknet_keccak(data: &[u8]) -> Felt {
+    let mut digest = keccak256(data);
+    digest[0] &= 0x03;
+
+    //smaller than 2^250 so always a valid felt
+    Felt::from_u256_reduced(U256::from_be_bytes(&digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+
+        assert_eq!(
+            hex::encode(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+
+        //exactly a block
+        assert_eq!(
+            hex::encode(keccak256(&[b'a'; 136])),
+            "a6c4d403279fe3e0af03729caada8374b5ca54d8065329a3ebcaeb4b60aa386e"
+        );
+
+        //longer than a block, absorbed in pieces
+        let mut hasher = Keccak256::default();
+        hasher.update(&[b'a'; 150]);
+        hasher.update(&[b'a'; 50]);
+        assert_eq!(
+            hex::encode(hasher.finalize()),
+            "96ea54061def936c4be90b518992fdc6f12f535068a256229aca54267b4d084d"
+        );
+    }
+
+    #[test]
+    fn selector() {
+        assert_eq!(
+            hex::encode(starknet_keccak(b"transfer").to_be_bytes()),
+            "0083afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e"
+        );
+    }
+}
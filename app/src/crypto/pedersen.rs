@@ -0,0 +1,165 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use bolos::PIC;
+
+use super::{
+    curve::{mul_bits, AffinePoint, ProjectivePoint},
+    felt::{Felt, U256},
+};
+
+/// Constant points of the pedersen hash: shift point, P1, P2, P3, P4
+///
+/// Coordinates are in montgomery form
+#[rustfmt::skip]
+const PEDERSEN_POINTS: [AffinePoint; 5] = [
+    AffinePoint::from_montgomery(
+        [0x1ad69b41a9ba0b3a, 0x6b69f758cd49de91, 0x16c727d5f24b5dc1, 0x0463d1e72d2ebf34],
+        [0xc5c9927f66d85eeb, 0xaeae324054290152, 0x4298f85b038ef6a8, 0x01211aac6ce572de],
+    ),
+    AffinePoint::from_montgomery(
+        [0x31fe19ef7e807b3f, 0xbeeff4924de3c528, 0x0732d950000368de, 0x035aa92df0885fd2],
+        [0xba89f77c4afe39a3, 0xb46ecbdd7b9728f2, 0x06055f47bdbf73e0, 0x051e9120dbb3de8a],
+    ),
+    AffinePoint::from_montgomery(
+        [0xe4deec837f33b9ce, 0xabd5caac208ecefb, 0x8b6346c265aee724, 0x0382d64c9967a198],
+        [0xc199e12f5f31cb95, 0x23536cf1caa6a1aa, 0x480b2d54a9d5af7c, 0x03f6c38c2c154983],
+    ),
+    AffinePoint::from_montgomery(
+        [0x10b47bd849ffb510, 0xfc851cc69e25ccc0, 0xb341405f34f13ada, 0x04b2a130122949c2],
+        [0xab6d4ef844e2ab95, 0xa2f96150f926753c, 0x99e3e86ec55f1fdf, 0x023c3adddadec10c],
+    ),
+    AffinePoint::from_montgomery(
+        [0x0fe61e5bdc6b2c54, 0x9400fdfe2acac5eb, 0x042909de8ae81d91, 0x05e7a88386446f6c],
+        [0x71a19251fe20ecd6, 0x3d75f33b9e7f39ac, 0x53bbfa8676fe82d4, 0x04cb4faae6091a14],
+    ),
+];
+
+/// Number of bits of an element processed by the "low" point
+const LOW_BITS: usize = 248;
+
+#[inline(never)]
+fn process_element(
+    acc: &ProjectivePoint,
+    element: &Felt,
+    low: &AffinePoint,
+    high: &AffinePoint,
+) -> ProjectivePoint {
+    let n = element.to_u256();
+    //bits 248..252, the element is smaller than 2^252
+    let high_part = U256::from_u64(n.0[3] >> 56);
+
+    acc.add(&mul_bits(low, &n, LOW_BITS))
+        .add(&mul_bits(high, &high_part, 4))
+}
+
+/// Computes the pedersen hash of `a` and `b`
+#[inline(never)]
+pub fn pedersen_hash(a: &Felt, b: &Felt) -> Felt {
+    let points = PIC::new(&PEDERSEN_POINTS).into_inner();
+
+    let acc = ProjectivePoint::from(&points[0]);
+    let acc = process_element(&acc, a, &points[1], &points[2]);
+    let acc = process_element(&acc, b, &points[3], &points[4]);
+
+    //the shift point is chosen such that the result is never the point at infinity
+    acc.to_affine().map(|p| p.x).unwrap_or(Felt::ZERO)
+}
+
+/// Computes the pedersen hash chain over `elements`, followed by the number of elements
+///
+/// This is what is usually referred as `compute_hash_on_elements`
+pub fn compute_hash_on_elements(elements: impl IntoIterator<Item = Felt>) -> Felt {
+    let mut hasher = PedersenHasher::default();
+    for e in elements {
+        hasher.update(&e);
+    }
+
+    hasher.finalize()
+}
+
+/// Incremental version of [`compute_hash_on_elements`]
+pub struct PedersenHasher {
+    state: Felt,
+    count: u64,
+}
+
+impl Default for PedersenHasher {
+    fn default() -> Self {
+        Self {
+            state: Felt::ZERO,
+            count: 0,
+        }
+    }
+}
+
+impl PedersenHasher {
+    pub fn update(&mut self, element: &Felt) {
+        self.state = pedersen_hash(&self.state, element);
+        self.count += 1;
+    }
+
+    pub fn finalize(self) -> Felt {
+        pedersen_hash(&self.state, &Felt::from_u64(self.count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn felt(s: &str) -> Felt {
+        let mut bytes = [0; 32];
+        let decoded = hex::decode(std::format!("{:0>64}", s)).unwrap();
+        bytes.copy_from_slice(&decoded);
+        Felt::from_be_bytes(&bytes).unwrap()
+    }
+
+    #[test]
+    fn hash() {
+        //test vectors from starkware-libs/crypto-cpp
+        let vectors = [
+            (
+                "03d937c035c878245caf64531a5756109c53068da139362728feb561405371cb",
+                "0208a0a10250e382e1e4bbe2880906c2791bf6275695e02fbbc6aeff9cd8b31a",
+                "030e480bed5fe53fa909cc0f8c4d99b8f9f2c016be4c41e13a4848797979c662",
+            ),
+            (
+                "058f580910a6ca59b28927c08fe6c43e2e303ca384badc365795fc645d479d45",
+                "078734f65a067be9bdb39de18434d71e79f7b6466a4b66bbd979ab9e7515fe0b",
+                "068cc0b76cddd1dd4ed2301ada9b7c872b23875d5ff837b3a87993e0d9996b87",
+            ),
+        ];
+
+        for (a, b, expected) in vectors.iter() {
+            assert_eq!(pedersen_hash(&felt(a), &felt(b)), felt(expected));
+        }
+    }
+
+    #[test]
+    fn hash_on_elements() {
+        //test vectors generated with cairo-lang
+        let elements = [0xaa, 0xbb, 0xcc, 0xdd].iter().map(|&n| Felt::from_u64(n));
+        assert_eq!(
+            compute_hash_on_elements(elements),
+            felt("025cde77210b1c223b2c6e69db6e9021aa1599177ab177474d5326cd2a62cb69")
+        );
+
+        assert_eq!(
+            compute_hash_on_elements(core::iter::empty()),
+            felt("049ee3eba8c1600700ee1b87eb599f16716b0b1022947733551fde4050ca6804")
+        );
+    }
+}
@@ -0,0 +1,197 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Deterministic ECDSA nonces (RFC 6979) with HMAC-SHA256
+//!
+//! Follows the Starknet tooling (starknet-rs, starknet.js), so the same key,
+//! hash and seed always give the same signature as those libraries
+
+use bolos::hash::{Hasher, Sha256};
+
+use super::{curve::CURVE_ORDER, felt::U256};
+use crate::utils::ApduPanic;
+
+const BLOCK: usize = 64;
+
+/// HMAC-SHA256, for keys of 32 bytes
+struct HmacSha256 {
+    key: [u8; 32],
+    inner: Sha256,
+}
+
+impl HmacSha256 {
+    fn padded_key(key: &[u8; 32], pad: u8) -> [u8; BLOCK] {
+        let mut padded = [pad; BLOCK];
+        for (p, k) in padded.iter_mut().zip(key.iter()) {
+            *p ^= k;
+        }
+
+        padded
+    }
+
+    fn new(key: &[u8; 32]) -> Self {
+        let mut inner = Sha256::new().apdu_unwrap();
+        inner.update(&Self::padded_key(key, 0x36)).apdu_unwrap();
+
+        Self { key: *key, inner }
+    }
+
+    fn update(&mut self, data: &[u8]) -> &mut Self {
+        self.inner.update(data).apdu_unwrap();
+        self
+    }
+
+    fn finalize(self) -> [u8; 32] {
+        let mut outer = Sha256::new().apdu_unwrap();
+        outer
+            .update(&Self::padded_key(&self.key, 0x5c))
+            .apdu_unwrap();
+        outer
+            .update(&self.inner.finalize().apdu_unwrap())
+            .apdu_unwrap();
+
+        outer.finalize().apdu_unwrap()
+    }
+}
+
+/// HMAC_DRBG state (`K` and `V` of RFC 6979 section 3.2)
+struct HmacDrbg {
+    k: [u8; 32],
+    v: [u8; 32],
+}
+
+impl HmacDrbg {
+    fn new(private_key: &[u8; 32], hash: &[u8; 32], data: &[u8]) -> Self {
+        let mut drbg = Self {
+            k: [0; 32],
+            v: [1; 32],
+        };
+
+        for i in 0..=1 {
+            let mut hmac = HmacSha256::new(&drbg.k);
+            hmac.update(&drbg.v)
+                .update(&[i])
+                .update(private_key)
+                .update(hash)
+                .update(data);
+            drbg.k = hmac.finalize();
+            drbg.v = drbg.hmac_v();
+        }
+
+        drbg
+    }
+
+    fn hmac_v(&self) -> [u8; 32] {
+        let mut hmac = HmacSha256::new(&self.k);
+        hmac.update(&self.v);
+        hmac.finalize()
+    }
+
+    fn next(&mut self) -> [u8; 32] {
+        self.v = self.hmac_v();
+        let out = self.v;
+
+        let mut hmac = HmacSha256::new(&self.k);
+        hmac.update(&self.v).update(&[0x00]);
+        self.k = hmac.finalize();
+        self.v = self.hmac_v();
+
+        out
+    }
+}
+
+/// Generates the nonce to sign `hash` with `private_key`
+///
+/// `seed` is extra entropy mixed in the generation, its leading zero bytes are ignored
+#[inline(never)]
+pub fn generate_k(hash: &[u8; 32], private_key: &[u8; 32], seed: Option<&[u8; 32]>) -> U256 {
+    let data = match seed {
+        Some(seed) => {
+            let start = seed.iter().position(|&b| b != 0).unwrap_or(seed.len());
+            &seed[start..]
+        }
+        None => &[],
+    };
+
+    let mut drbg = HmacDrbg::new(private_key, hash, data);
+    loop {
+        //keep the leftmost 252 bits, the bit length of the order
+        let k = U256::from_be_bytes(&drbg.next()).shr_small(4);
+        if !k.is_zero() && k < CURVE_ORDER {
+            return k;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(s: &str) -> [u8; 32] {
+        let mut out = [0; 32];
+        out.copy_from_slice(&hex::decode(s).unwrap());
+        out
+    }
+
+    #[test]
+    fn hmac() {
+        //RFC 4231, test case 2 with the key padded to 32 bytes
+        let mut key = [0; 32];
+        key[..4].copy_from_slice(b"Jefe");
+        let mut hmac = HmacSha256::new(&key);
+        hmac.update(b"what do ya want ").update(b"for nothing?");
+
+        assert_eq!(
+            hex::encode(hmac.finalize()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn starknet_crypto_vectors() {
+        let private_key = bytes("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let hash = bytes("0397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f");
+
+        assert_eq!(
+            hex::encode(generate_k(&hash, &private_key, None).to_be_bytes()),
+            "050a50e20a9fb5b33f618ce4ddec8df60f40d3ac3018453bcc002cee71140cd4"
+        );
+
+        let mut seed = [0; 32];
+        seed[31] = 42;
+        assert_eq!(
+            hex::encode(generate_k(&hash, &private_key, Some(&seed)).to_be_bytes()),
+            "00203aee542859dad53d46e6d258617ff646ad8625d0d32b67a129fbd3aac9c0"
+        );
+
+        let mut one = [0; 32];
+        one[31] = 1;
+        assert_eq!(
+            hex::encode(generate_k(&one, &one, None).to_be_bytes()),
+            "02a3e2067f05114b62c785475a65b6c4a698b55f8707de4d3fe4cdd6238691ff"
+        );
+    }
+
+    #[test]
+    fn zero_seed_is_no_seed() {
+        let private_key = bytes("03c1e9550e66958296d11b60f8e8e7a7ad990d07fa65d5f7652c4a6c87d4e3cc");
+        let hash = bytes("0397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f");
+
+        assert!(
+            generate_k(&hash, &private_key, Some(&[0; 32]))
+                == generate_k(&hash, &private_key, None)
+        );
+    }
+}
@@ -0,0 +1,324 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Stark ECDSA signatures, as returned by the app
+//!
+//! Signatures are encoded as R || S || V (65 bytes):
+//! * R and S, 32 bytes each, big-endian
+//! * V, the parity of the y coordinate of the nonce point (whose x coordinate is R),
+//!   which is what lets the public key be recovered from the signature
+//!
+//! Both S and n - S verify, and negating S also negates the nonce point,
+//! so V flips with it. Stark verifiers (starknet-crypto, Cairo's `check_ecdsa_signature`)
+//! only accept S and S^-1 below 2^251: of the two forms, the one with the lowest S
+//! is used, unless only the other one is accepted.
+
+use bolos::PIC;
+
+use super::{
+    curve::{mul, AffinePoint, Scalar, CURVE_ORDER, GENERATOR},
+    ecdsa::{in_element_range, message_scalar},
+    felt::{Felt, U256},
+};
+
+/// (n - 1) / 2, the largest low S
+const HALF_ORDER: U256 = U256([
+    0x0f335120d6e32697,
+    0xdbc08936e573d919,
+    0x7fffffffffffffff,
+    0x0400000000000008,
+]);
+
+/// Stark ECDSA signature, with the parity of the y coordinate of R as `v`
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub struct Signature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    pub v: u8,
+}
+
+impl Signature {
+    /// Length of the R || S || V encoding
+    pub const LEN: usize = 65;
+
+    /// Reads an R || S || V encoded signature
+    ///
+    /// Returns `None` if V is neither 0 nor 1
+    pub fn from_rsv(rsv: &[u8; Self::LEN]) -> Option<Self> {
+        let v = rsv[64];
+        if v > 1 {
+            return None;
+        }
+
+        Some(Self {
+            r: *arrayref::array_ref![rsv, 0, 32],
+            s: *arrayref::array_ref![rsv, 32, 32],
+            v,
+        })
+    }
+
+    /// Writes the signature as R || S || V
+    pub fn to_rsv(&self) -> [u8; Self::LEN] {
+        let mut out = [0; Self::LEN];
+        out[..32].copy_from_slice(&self.r);
+        out[32..64].copy_from_slice(&self.s);
+        out[64] = self.v;
+
+        out
+    }
+
+    /// Returns true if S is in the lower half of the curve order
+    pub fn is_low_s(&self) -> bool {
+        U256::from_be_bytes(&self.s) <= HALF_ORDER
+    }
+
+    /// Returns true if Stark verifiers accept the components of the signature
+    ///
+    /// R, S and S^-1 must all be in [1, 2^251)
+    pub fn is_stark_valid(&self) -> bool {
+        let s = U256::from_be_bytes(&self.s);
+        if !in_element_range(&U256::from_be_bytes(&self.r)) || !in_element_range(&s) {
+            return false;
+        }
+
+        Scalar::from_u256(s)
+            .and_then(|s| s.inverse())
+            .map(|w| in_element_range(&w.to_u256()))
+            .unwrap_or(false)
+    }
+
+    /// Returns the other valid signature, with n - S and the opposite V
+    pub fn negate(&self) -> Self {
+        let s = U256::from_be_bytes(&self.s);
+        let (s, _) = CURVE_ORDER.overflowing_sub(&s);
+
+        Self {
+            r: self.r,
+            s: s.to_be_bytes(),
+            v: self.v ^ 1,
+        }
+    }
+
+    /// Puts the signature in its canonical form, see the module documentation
+    ///
+    /// Returns `None` if neither form is accepted by Stark verifiers
+    #[inline(never)]
+    pub fn normalize(self) -> Option<Self> {
+        let (low, high) = if self.is_low_s() {
+            (self, self.negate())
+        } else {
+            (self.negate(), self)
+        };
+
+        if low.is_stark_valid() {
+            Some(low)
+        } else if high.is_stark_valid() {
+            Some(high)
+        } else {
+            None
+        }
+    }
+}
+
+/// Recovers the public key that made the signature of `hash`
+///
+/// Returns `None` if the signature is malformed or no key can have made it
+#[inline(never)]
+pub fn recover_public_key(hash: &[u8; 32], signature: &Signature) -> Option<AffinePoint> {
+    if signature.v > 1 {
+        return None;
+    }
+
+    let r = U256::from_be_bytes(&signature.r);
+    if !in_element_range(&r) {
+        return None;
+    }
+    let s = Scalar::from_u256(U256::from_be_bytes(&signature.s)).filter(|s| !s.is_zero())?;
+
+    //r < 2^251 is both a field element and a scalar
+    let point = AffinePoint::from_x(Felt::from_u256(r)?, signature.v == 1)?;
+    let r_inv = Scalar::from_u256(r)?.inverse()?;
+
+    //Q = r^-1 * (s * R - z * G)
+    let u1 = message_scalar(hash).neg().mul(&r_inv);
+    let u2 = s.mul(&r_inv);
+
+    let generator = PIC::new(&GENERATOR).into_inner();
+    mul(generator, &u1.to_u256())
+        .add(&mul(&point, &u2.to_u256()))
+        .to_affine()
+}
+
+/// Recovers the public key (its x coordinate, as used by Starknet) that signed `hash`
+///
+/// `v` is the parity emitted by the app alongside R and S
+pub fn recover(hash: &[u8; 32], r: &[u8; 32], s: &[u8; 32], v: u8) -> Option<[u8; 32]> {
+    let signature = Signature { r: *r, s: *s, v };
+
+    recover_public_key(hash, &signature).map(|point| point.x.to_be_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::ecdsa::sign;
+    use bolos::hash::{Hasher, Sha256};
+
+    use starknet_crypto::FieldElement;
+
+    fn field_element(bytes: &[u8; 32]) -> FieldElement {
+        FieldElement::from_bytes_be(bytes).unwrap()
+    }
+
+    /// Deterministic stream of private keys and hashes
+    fn cases(n: u8) -> impl Iterator<Item = (Scalar, [u8; 32])> {
+        (0..n).map(|i| {
+            let key = Sha256::digest(&[b'k', i]).unwrap();
+            let mut hash = Sha256::digest(&[b'h', i]).unwrap();
+            hash[0] &= 0x07;
+
+            (Scalar::from_u256_reduced(U256::from_be_bytes(&key)), hash)
+        })
+    }
+
+    fn public_key(private_key: &Scalar) -> AffinePoint {
+        let generator = PIC::new(&GENERATOR).into_inner();
+        mul(generator, &private_key.to_u256()).to_affine().unwrap()
+    }
+
+    #[test]
+    fn half_order() {
+        let (double, _) = HALF_ORDER.overflowing_mul_small(2);
+        assert!(double.overflowing_add(&U256::ONE).0 == CURVE_ORDER);
+    }
+
+    #[test]
+    fn rsv_roundtrip() {
+        let mut rsv = [0x11; Signature::LEN];
+        rsv[64] = 1;
+
+        let signature = Signature::from_rsv(&rsv).unwrap();
+        assert_eq!(signature.v, 1);
+        assert_eq!(&signature.to_rsv()[..], &rsv[..]);
+
+        rsv[64] = 2;
+        assert!(Signature::from_rsv(&rsv).is_none());
+    }
+
+    #[test]
+    fn normalizes_to_low_s() {
+        for (key, hash) in cases(16) {
+            let signature = sign(&key, &hash, None).unwrap();
+            let normalized = signature.normalize().unwrap();
+
+            assert!(normalized.is_low_s());
+            assert!(normalized.is_stark_valid());
+            assert_eq!(normalized.r, signature.r);
+            if signature.is_low_s() {
+                assert_eq!(normalized, signature);
+            } else {
+                assert_eq!(normalized, signature.negate());
+            }
+
+            //idempotent
+            assert_eq!(normalized.normalize(), Some(normalized));
+        }
+    }
+
+    #[test]
+    fn verifies_with_starknet_crypto() {
+        for (key, hash) in cases(16) {
+            let signature = sign(&key, &hash, None).unwrap().normalize().unwrap();
+            let public_key = public_key(&key);
+
+            assert!(starknet_crypto::verify(
+                &field_element(&public_key.x.to_be_bytes()),
+                &field_element(&hash),
+                &field_element(&signature.r),
+                &field_element(&signature.s),
+            )
+            .unwrap());
+
+            let recovered = starknet_crypto::recover(
+                &field_element(&hash),
+                &field_element(&signature.r),
+                &field_element(&signature.s),
+                &FieldElement::from(signature.v),
+            )
+            .unwrap();
+            assert_eq!(recovered.to_bytes_be(), public_key.x.to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn recovers_public_key() {
+        for (key, hash) in cases(16) {
+            let signature = sign(&key, &hash, None).unwrap();
+            let public_key = public_key(&key);
+
+            assert_eq!(recover_public_key(&hash, &signature), Some(public_key));
+            assert_eq!(
+                recover_public_key(&hash, &signature.normalize().unwrap()),
+                Some(public_key)
+            );
+
+            //the wrong parity gives the negated nonce point, and so another key
+            let mut flipped = signature;
+            flipped.v ^= 1;
+            assert_ne!(recover_public_key(&hash, &flipped), Some(public_key));
+        }
+    }
+
+    #[test]
+    fn recovers_x_coordinate() {
+        for (key, hash) in cases(8) {
+            //as returned by the app
+            let rsv = sign(&key, &hash, None)
+                .unwrap()
+                .normalize()
+                .unwrap()
+                .to_rsv();
+            let signature = Signature::from_rsv(&rsv).unwrap();
+
+            let x = recover(&hash, &signature.r, &signature.s, signature.v).unwrap();
+            assert_eq!(x, public_key(&key).x.to_be_bytes());
+            assert_eq!(
+                x,
+                starknet_crypto::get_public_key(&field_element(&key.to_be_bytes())).to_bytes_be()
+            );
+        }
+    }
+
+    #[test]
+    fn refuses_malformed_signatures() {
+        let (key, hash) = cases(1).next().unwrap();
+        let signature = sign(&key, &hash, None).unwrap();
+
+        let mut wrong = signature;
+        wrong.v = 2;
+        assert!(recover_public_key(&hash, &wrong).is_none());
+
+        let mut wrong = signature;
+        wrong.r = [0; 32];
+        assert!(recover_public_key(&hash, &wrong).is_none());
+        assert!(!wrong.is_stark_valid());
+
+        let mut wrong = signature;
+        wrong.s = CURVE_ORDER.to_be_bytes();
+        assert!(recover_public_key(&hash, &wrong).is_none());
+        assert!(wrong.normalize().is_none());
+    }
+}
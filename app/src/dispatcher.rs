@@ -18,10 +18,18 @@ use core::hint::unreachable_unchecked;
 
 use crate::constants::{instructions::*, ApduError};
 
+use crate::handlers::configuration::GetAppConfiguration;
 use crate::handlers::public_key::GetPublicKey;
-use crate::handlers::signing::{Sign, SignFelt};
+use crate::handlers::response::GetNextChunk;
+use crate::handlers::signing::{
+    Sign, SignDeclare, SignDeclareV3, SignDeployAccount, SignDeployAccountV3, SignFelt,
+    SignFeltBatch, SignInvoke, SignInvokeV3, SignStarkExOrder, SignStarkExTransfer, SignTypedData,
+};
 use crate::handlers::version::GetVersion;
 
+#[cfg(feature = "pubkey-recovery")]
+use crate::handlers::recover::RecoverPublicKey;
+
 #[cfg(feature = "dev")]
 use crate::handlers::dev::*;
 
@@ -55,8 +63,22 @@ pub fn apdu_dispatch<'apdu>(
     match ins {
         INS_GET_VERSION => GetVersion::handle(flags, tx, apdu_buffer),
         INS_GET_PUBLIC_KEY => GetPublicKey::handle(flags, tx, apdu_buffer),
+        INS_GET_NEXT_CHUNK => GetNextChunk::handle(flags, tx, apdu_buffer),
+        INS_GET_APP_CONFIGURATION => GetAppConfiguration::handle(flags, tx, apdu_buffer),
         INS_SIGN => Sign::handle(flags, tx, apdu_buffer),
         INS_SIGN_FELT => SignFelt::handle(flags, tx, apdu_buffer),
+        INS_SIGN_FELT_BATCH => SignFeltBatch::handle(flags, tx, apdu_buffer),
+        INS_SIGN_INVOKE => SignInvoke::handle(flags, tx, apdu_buffer),
+        INS_SIGN_INVOKE_V3 => SignInvokeV3::handle(flags, tx, apdu_buffer),
+        INS_SIGN_TYPED_DATA => SignTypedData::handle(flags, tx, apdu_buffer),
+        INS_SIGN_DEPLOY_ACCOUNT => SignDeployAccount::handle(flags, tx, apdu_buffer),
+        INS_SIGN_DEPLOY_ACCOUNT_V3 => SignDeployAccountV3::handle(flags, tx, apdu_buffer),
+        INS_SIGN_DECLARE => SignDeclare::handle(flags, tx, apdu_buffer),
+        INS_SIGN_DECLARE_V3 => SignDeclareV3::handle(flags, tx, apdu_buffer),
+        INS_SIGN_STARKEX_ORDER => SignStarkExOrder::handle(flags, tx, apdu_buffer),
+        INS_SIGN_STARKEX_TRANSFER => SignStarkExTransfer::handle(flags, tx, apdu_buffer),
+        #[cfg(feature = "pubkey-recovery")]
+        INS_RECOVER_PUBLIC_KEY => RecoverPublicKey::handle(flags, tx, apdu_buffer),
 
         #[cfg(feature = "dev")]
         _ => Debug::handle(flags, tx, apdu_buffer),
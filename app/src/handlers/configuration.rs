@@ -0,0 +1,264 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Settings and capabilities of the app, so hosts can adapt to it
+//!
+//! The configuration starts with its format version,
+//! followed by tag (1 byte), length (1 byte), value entries.
+//! Hosts must skip the tags they don't know
+use crate::{
+    constants::{instructions::*, ApduError as Error},
+    dispatcher::ApduHandler,
+    handlers::{
+        resources::BUFFER_FLASH_LEN,
+        signing::{blind_sign_enabled, testnet_sign_enabled},
+    },
+    parser::{
+        DeclareV2, DeclareV3, DeployAccountV1, DeployAccountV3, InvokeV1, InvokeV3, TypedData,
+    },
+    sys,
+    utils::ApduBufferRead,
+};
+
+/// Returns the settings and capabilities of the app
+pub struct GetAppConfiguration;
+
+impl GetAppConfiguration {
+    pub const FORMAT_VERSION: u8 = 1;
+
+    /// Settings enabled by the user, see the `SETTING_` flags
+    pub const TAG_SETTINGS: u8 = 0x01;
+    /// Features the app was built with, see the `FEATURE_` flags
+    pub const TAG_FEATURES: u8 = 0x02;
+    /// Bitmap of the supported instructions, bit `INS % 8` of byte `INS / 8`
+    pub const TAG_INSTRUCTIONS: u8 = 0x03;
+    /// (INS, version) pairs of the transaction version
+    /// (or typed data revision) signed by each instruction
+    pub const TAG_PARSER_VERSIONS: u8 = 0x04;
+    /// Maximum number of bytes uploaded by a command, big endian u16
+    pub const TAG_MAX_UPLOAD: u8 = 0x05;
+
+    pub const SETTING_BLIND_SIGNING: u8 = 1 << 0;
+    pub const SETTING_TESTNET_SIGNING: u8 = 1 << 1;
+
+    pub const FEATURE_BLIND_SIGN_TOGGLE: u8 = 1 << 0;
+    pub const FEATURE_TESTNET_TOGGLE: u8 = 1 << 1;
+    pub const FEATURE_PUBKEY_RECOVERY: u8 = 1 << 2;
+    pub const FEATURE_DEV: u8 = 1 << 3;
+
+    /// The first byte of `BUFFER` holds the P2 of the first packet
+    pub const MAX_UPLOAD: usize = BUFFER_FLASH_LEN - 1;
+
+    fn settings() -> u8 {
+        let mut settings = 0;
+        if blind_sign_enabled() {
+            settings |= Self::SETTING_BLIND_SIGNING;
+        }
+        if testnet_sign_enabled() {
+            settings |= Self::SETTING_TESTNET_SIGNING;
+        }
+
+        settings
+    }
+
+    pub(crate) fn features() -> u8 {
+        let mut features = 0;
+        if cfg!(feature = "blind-sign-toggle") {
+            features |= Self::FEATURE_BLIND_SIGN_TOGGLE;
+        }
+        if cfg!(feature = "testnet-toggle") {
+            features |= Self::FEATURE_TESTNET_TOGGLE;
+        }
+        if cfg!(feature = "pubkey-recovery") {
+            features |= Self::FEATURE_PUBKEY_RECOVERY;
+        }
+        if cfg!(feature = "dev") {
+            features |= Self::FEATURE_DEV;
+        }
+
+        features
+    }
+
+    fn instructions() -> [u8; 32] {
+        let instructions = [
+            INS_GET_VERSION,
+            INS_GET_PUBLIC_KEY,
+            INS_GET_NEXT_CHUNK,
+            INS_GET_APP_CONFIGURATION,
+            INS_SIGN,
+            INS_SIGN_FELT,
+            INS_SIGN_FELT_BATCH,
+            INS_SIGN_INVOKE,
+            INS_SIGN_INVOKE_V3,
+            INS_SIGN_TYPED_DATA,
+            INS_SIGN_DEPLOY_ACCOUNT,
+            INS_SIGN_DEPLOY_ACCOUNT_V3,
+            INS_SIGN_DECLARE,
+            INS_SIGN_DECLARE_V3,
+            INS_SIGN_STARKEX_ORDER,
+            INS_SIGN_STARKEX_TRANSFER,
+        ];
+
+        let mut bitmap = [0; 32];
+        let mut set = |ins: u8| bitmap[ins as usize / 8] |= 1 << (ins % 8);
+
+        instructions.iter().for_each(|&ins| set(ins));
+        if cfg!(feature = "pubkey-recovery") {
+            set(INS_RECOVER_PUBLIC_KEY);
+        }
+
+        bitmap
+    }
+
+    fn parser_versions() -> [u8; 14] {
+        let versions = [
+            (INS_SIGN_INVOKE, InvokeV1::VERSION),
+            (INS_SIGN_INVOKE_V3, InvokeV3::VERSION),
+            (INS_SIGN_DEPLOY_ACCOUNT, DeployAccountV1::VERSION),
+            (INS_SIGN_DEPLOY_ACCOUNT_V3, DeployAccountV3::VERSION),
+            (INS_SIGN_DECLARE, DeclareV2::VERSION),
+            (INS_SIGN_DECLARE_V3, DeclareV3::VERSION),
+            (INS_SIGN_TYPED_DATA, TypedData::REVISION),
+        ];
+
+        let mut out = [0; 14];
+        for (pair, &(ins, version)) in out.chunks_exact_mut(2).zip(versions.iter()) {
+            pair[0] = ins;
+            pair[1] = version as u8;
+        }
+
+        out
+    }
+
+    /// Writes the configuration to `out`, returning its length
+    #[inline(never)]
+    fn write(out: &mut [u8]) -> Result<usize, Error> {
+        let mut writer = TlvWriter { out, len: 0 };
+
+        *writer.out.first_mut().ok_or(Error::OutputBufferTooSmall)? = Self::FORMAT_VERSION;
+        writer.len = 1;
+
+        writer.push(Self::TAG_SETTINGS, &[Self::settings()])?;
+        writer.push(Self::TAG_FEATURES, &[Self::features()])?;
+        writer.push(Self::TAG_INSTRUCTIONS, &Self::instructions())?;
+        writer.push(Self::TAG_PARSER_VERSIONS, &Self::parser_versions())?;
+        writer.push(
+            Self::TAG_MAX_UPLOAD,
+            &(Self::MAX_UPLOAD as u16).to_be_bytes(),
+        )?;
+
+        Ok(writer.len)
+    }
+}
+
+struct TlvWriter<'b> {
+    out: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> TlvWriter<'b> {
+    fn push(&mut self, tag: u8, value: &[u8]) -> Result<(), Error> {
+        let end = self.len + 2 + value.len();
+        let entry = self
+            .out
+            .get_mut(self.len..end)
+            .ok_or(Error::OutputBufferTooSmall)?;
+
+        entry[0] = tag;
+        entry[1] = value.len() as u8;
+        entry[2..].copy_from_slice(value);
+        self.len = end;
+
+        Ok(())
+    }
+}
+
+impl ApduHandler for GetAppConfiguration {
+    #[inline(never)]
+    fn handle<'apdu>(
+        _: &mut u32,
+        tx: &mut u32,
+        buffer: ApduBufferRead<'apdu>,
+    ) -> Result<(), Error> {
+        sys::zemu_log_stack("GetAppConfiguration::handle\x00");
+
+        *tx = 0;
+        *tx = Self::write(buffer.write())? as u32;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_error_code, constants::ApduError, handle_apdu_raw};
+    use std::convert::TryInto;
+
+    /// Returns the value of `tag` in the configuration
+    fn entry(config: &[u8], tag: u8) -> Option<&[u8]> {
+        let mut entries = &config[1..];
+        while let [t, len, rest @ ..] = entries {
+            let (value, next) = rest.split_at(*len as usize);
+            if *t == tag {
+                return Some(value);
+            }
+            entries = next;
+        }
+
+        None
+    }
+
+    fn supports(bitmap: &[u8], ins: u8) -> bool {
+        bitmap[ins as usize / 8] & (1 << (ins % 8)) != 0
+    }
+
+    #[test]
+    fn configuration() {
+        let (_, tx, out) = handle_apdu_raw(&[CLA, INS_GET_APP_CONFIGURATION, 0, 0, 0]);
+        assert_error_code!(tx, out, ApduError::Success);
+
+        let config = &out[..tx as usize - 2];
+        assert_eq!(config[0], GetAppConfiguration::FORMAT_VERSION);
+
+        //blind signing is enabled and testnet signing disabled in the mocks
+        let settings = entry(config, GetAppConfiguration::TAG_SETTINGS).unwrap();
+        assert_eq!(settings, [GetAppConfiguration::SETTING_BLIND_SIGNING]);
+
+        let features = entry(config, GetAppConfiguration::TAG_FEATURES).unwrap()[0];
+        assert_eq!(
+            features & GetAppConfiguration::FEATURE_PUBKEY_RECOVERY != 0,
+            cfg!(feature = "pubkey-recovery")
+        );
+
+        let bitmap = entry(config, GetAppConfiguration::TAG_INSTRUCTIONS).unwrap();
+        assert!(supports(bitmap, INS_SIGN_FELT_BATCH));
+        assert!(supports(bitmap, INS_GET_APP_CONFIGURATION));
+        assert_eq!(
+            supports(bitmap, INS_RECOVER_PUBLIC_KEY),
+            cfg!(feature = "pubkey-recovery")
+        );
+        assert!(!supports(bitmap, 0xFF));
+
+        let versions = entry(config, GetAppConfiguration::TAG_PARSER_VERSIONS).unwrap();
+        assert!(versions.chunks(2).any(|pair| pair == [INS_SIGN_DECLARE, 2]));
+        assert!(versions
+            .chunks(2)
+            .any(|pair| pair == [INS_SIGN_INVOKE_V3, 3]));
+
+        let max_upload = entry(config, GetAppConfiguration::TAG_MAX_UPLOAD).unwrap();
+        assert_eq!(max_upload, 0x1FFEu16.to_be_bytes());
+    }
+}
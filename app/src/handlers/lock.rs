@@ -18,6 +18,7 @@ use crate::constants::ApduError;
 pub struct Lock<T, A> {
     item: T,
     lock: Option<A>,
+    pinned: bool,
 }
 
 #[cfg_attr(test, derive(Debug))]
@@ -29,13 +30,21 @@ pub enum LockError {
 
 impl<T, A> Lock<T, A> {
     pub const fn new(item: T) -> Self {
-        Self { item, lock: None }
+        Self {
+            item,
+            lock: None,
+            pinned: false,
+        }
     }
 }
 
 impl<T, A: Eq> Lock<T, A> {
     ///Locks the resource (if available) and retrieve it
     pub fn lock(&mut self, acquirer: impl Into<A>) -> Result<&mut T, LockError> {
+        if self.pinned {
+            return Err(LockError::Busy);
+        }
+
         let acq = acquirer.into();
         match self.lock {
             Some(ref a) if a == &acq => Ok(&mut self.item),
@@ -49,6 +58,10 @@ impl<T, A: Eq> Lock<T, A> {
 
     ///Acquire the resource if locked by `acquirer`
     pub fn acquire(&mut self, acquirer: impl Into<A>) -> Result<&mut T, LockError> {
+        if self.pinned {
+            return Err(LockError::Busy);
+        }
+
         let acq = acquirer.into();
         match self.lock {
             Some(ref a) if a == &acq => Ok(&mut self.item),
@@ -57,12 +70,41 @@ impl<T, A: Eq> Lock<T, A> {
         }
     }
 
+    ///Keep the resource locked by `acquirer` and its content untouched
+    ///
+    ///Until `acquirer` unpins it, nobody (not even `acquirer`) can lock or acquire it
+    pub fn pin(&mut self, acquirer: impl Into<A>) -> Result<(), LockError> {
+        let acq = acquirer.into();
+        match self.lock {
+            Some(ref a) if a == &acq => {
+                self.pinned = true;
+                Ok(())
+            }
+            Some(_) => Err(LockError::BadId),
+            None => Err(LockError::NotLocked),
+        }
+    }
+
+    ///Allow the resource to be acquired again, if pinned by `acquirer`
+    pub fn unpin(&mut self, acquirer: impl Into<A>) -> Result<(), LockError> {
+        let acq = acquirer.into();
+        match self.lock {
+            Some(ref a) if a == &acq => {
+                self.pinned = false;
+                Ok(())
+            }
+            Some(_) => Err(LockError::BadId),
+            None => Err(LockError::NotLocked),
+        }
+    }
+
     ///Release the resource if locker by `acquirer`
     pub fn release(&mut self, acquirer: impl Into<A>) -> Result<(), LockError> {
         let acq = acquirer.into();
         match self.lock {
             Some(ref a) if a == &acq => {
                 self.lock = None;
+                self.pinned = false;
                 Ok(())
             }
             Some(_) => Err(LockError::BadId),
@@ -126,4 +168,25 @@ mod tests {
         lock.acquire(0).unwrap_err();
         lock.acquire(1).unwrap();
     }
+
+    #[test]
+    fn pinned_lock() {
+        let mut lock = build_lock(3);
+        lock.lock(0).unwrap();
+        lock.pin(1).unwrap_err();
+        lock.pin(0).unwrap();
+
+        //not even the owner can get it while pinned
+        lock.lock(1).unwrap_err();
+        lock.lock(0).unwrap_err();
+        lock.acquire(0).unwrap_err();
+
+        lock.unpin(1).unwrap_err();
+        lock.unpin(0).unwrap();
+        lock.acquire(0).unwrap();
+
+        lock.pin(0).unwrap();
+        lock.release(0).unwrap();
+        lock.lock(1).unwrap();
+    }
 }
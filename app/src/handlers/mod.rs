@@ -13,10 +13,15 @@
 *  See the License for the specific language governing permissions and
 *  limitations under the License.
 ********************************************************************************/
+pub mod configuration;
 pub mod public_key;
+pub mod response;
 pub mod signing;
 pub mod version;
 
+#[cfg(feature = "pubkey-recovery")]
+pub mod recover;
+
 #[cfg(feature = "dev")]
 pub mod dev;
 
@@ -27,29 +32,64 @@ pub mod resources {
     use super::lock::Lock;
     use bolos::{PIC, lazy_static, new_swapping_buffer, SwappingBuffer};
 
+    /// Size of the RAM part of `BUFFER`
+    pub const BUFFER_RAM_LEN: usize = 0xFF;
+
+    /// Size of the flash part of `BUFFER`, used once the RAM part is full
+    pub const BUFFER_FLASH_LEN: usize = 0x1FFF;
+
     #[lazy_static]
-    pub static mut BUFFER: Lock<SwappingBuffer<'static, 'static, 0xFF, 0x1FFF>, BUFFERAccessors> =
-        Lock::new(new_swapping_buffer!(0xFF, 0x1FFF));
+    pub static mut BUFFER: Lock<
+        SwappingBuffer<'static, 'static, BUFFER_RAM_LEN, BUFFER_FLASH_LEN>,
+        BUFFERAccessors,
+    > = Lock::new(new_swapping_buffer!(BUFFER_RAM_LEN, BUFFER_FLASH_LEN));
 
     #[derive(Clone, Copy, PartialEq, Eq)]
     pub enum BUFFERAccessors {
         Sign,
         SignFelt,
+        SignFeltBatch,
+        SignInvoke,
+        SignInvokeV3,
+        SignTypedData,
+        SignDeployAccount,
+        SignDeployAccountV3,
+        SignDeclare,
+        SignDeclareV3,
+        SignStarkExOrder,
+        SignStarkExTransfer,
+        Response,
         #[cfg(feature = "dev")]
         Debug,
     }
 
-    impl From<super::signing::Sign> for BUFFERAccessors {
-        fn from(_: super::signing::Sign) -> Self {
-            Self::Sign
-        }
+    /// Implements the conversion of each signing handler to its accessor of the same name
+    macro_rules! signing_accessors {
+        ($($handler:ident),* $(,)?) => {
+            $(
+                impl From<super::signing::$handler> for BUFFERAccessors {
+                    fn from(_: super::signing::$handler) -> Self {
+                        Self::$handler
+                    }
+                }
+            )*
+        };
     }
 
-    impl From<super::signing::SignFelt> for BUFFERAccessors {
-        fn from(_: super::signing::SignFelt) -> Self {
-            Self::SignFelt
-        }
-    }
+    signing_accessors!(
+        Sign,
+        SignFelt,
+        SignFeltBatch,
+        SignInvoke,
+        SignInvokeV3,
+        SignTypedData,
+        SignDeployAccount,
+        SignDeployAccountV3,
+        SignDeclare,
+        SignDeclareV3,
+        SignStarkExOrder,
+        SignStarkExTransfer,
+    );
 
     #[cfg(feature = "dev")]
     impl From<super::dev::Debug> for BUFFERAccessors {
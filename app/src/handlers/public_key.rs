@@ -20,10 +20,12 @@ use zemu_sys::{Show, ViewError, Viewable};
 
 use crate::{
     constants::{ApduError as Error, BIP32_MAX_LENGTH},
-    crypto,
+    crypto::{self, Felt},
     dispatcher::ApduHandler,
     handlers::{handle_ui_message, verify_bip32_path},
-    sys::{self, Error as SysError},
+    parser::{render_address, AccountLayout, AccountParams, DisplayableItem},
+    path::PathItems,
+    sys::{self, crypto::bip32::BIP32Path, Error as SysError},
     utils::{hex_encode, ApduBufferRead, ApduPanic},
 };
 
@@ -59,11 +61,27 @@ impl ApduHandler for GetPublicKey {
         let curve = crypto::Curve::Stark256;
 
         let cdata = buffer.payload().map_err(|_| Error::DataInvalid)?;
+
+        //the path can be followed by the parameters of the account
+        let path_len = 1 + 4 * *cdata.first().ok_or(Error::DataInvalid)? as usize;
+        if cdata.len() < path_len {
+            return Err(Error::DataInvalid);
+        }
+        let (cdata, account_data) = cdata.split_at(path_len);
+
         let bip32_path = sys::crypto::bip32::BIP32Path::<{ BIP32_MAX_LENGTH }>::read(cdata)
             .map_err(|_| Error::DataInvalid)?;
 
         verify_bip32_path(&bip32_path)?;
 
+        let account = match buffer.p2() {
+            0 => None,
+            p2 => {
+                let layout = AccountLayout::try_from(p2).map_err(|_| Error::InvalidP1P2)?;
+                Some(AccountParams::parse(layout, account_data)?)
+            }
+        };
+
         let mut ui = MaybeUninit::<AddrUI>::uninit();
 
         //initialize public key
@@ -77,12 +95,23 @@ impl ApduHandler for GetPublicKey {
             let key =
                 unsafe { addr_of_mut!((*ui).pkey).cast::<MaybeUninit<_>>().as_mut() }.apdu_unwrap();
             Self::new_key_into(curve, &bip32_path, key).map_err(|_| Error::ExecutionError)?;
+
+            //SAFE: the pointers are valid and aligned, they're just not initialized yet
+            unsafe {
+                addr_of_mut!((*ui).account).write(None);
+                addr_of_mut!((*ui).path).write(bip32_path);
+            }
         }
 
         //safe because it's all initialized now
         // even tho the hash isn't, we aren't gonna read from it
         let mut ui = unsafe { ui.assume_init() };
 
+        if let Some(params) = account {
+            let pkey = Felt::from_be_bytes(ui.pkey.x()).ok_or(Error::ExecutionError)?;
+            ui.account = Some((params.layout, params.address(&pkey)));
+        }
+
         if req_confirmation {
             match unsafe { ui.show(flags) } {
                 Ok((val, code)) => {
@@ -113,11 +142,16 @@ impl ApduHandler for GetPublicKey {
 
 pub struct AddrUI {
     pub pkey: crypto::PublicKey,
+    pub path: BIP32Path<BIP32_MAX_LENGTH>,
+    /// Counterfactual address of the account of `pkey`, if requested
+    pub account: Option<(AccountLayout, Felt)>,
 }
 
 impl Viewable for AddrUI {
     fn num_items(&mut self) -> Result<u8, ViewError> {
-        Ok(1)
+        let n_path = PathItems::new(self.path.components()).num_items() as u8;
+
+        Ok(n_path + 1 + self.account.is_some() as u8)
     }
 
     fn render_item(
@@ -129,6 +163,13 @@ impl Viewable for AddrUI {
     ) -> Result<u8, ViewError> {
         use bolos::{pic_str, PIC};
 
+        let path_items = PathItems::new(self.path.components());
+        let n_path = path_items.num_items() as u8;
+        if item_n < n_path {
+            return path_items.render_item(item_n, title, message, page);
+        }
+        let item_n = item_n - n_path;
+
         if let 0 = item_n {
             let title_content = pic_str!(b"Public Key");
             title[..title_content.len()].copy_from_slice(title_content);
@@ -137,6 +178,11 @@ impl Viewable for AddrUI {
             let len = hex_encode(self.pkey.as_ref(), &mut mex).apdu_unwrap();
 
             handle_ui_message(&mex[..len], message, page)
+        } else if let (1, Some((layout, address))) = (item_n, self.account) {
+            let title_content = layout.label();
+            title[..title_content.len()].copy_from_slice(title_content);
+
+            render_address(&address.to_be_bytes(), message, page)
         } else {
             Err(ViewError::NoData)
         }
@@ -151,6 +197,11 @@ impl Viewable for AddrUI {
         out[tx..tx + pkey.len()].copy_from_slice(pkey);
         tx += pkey.len();
 
+        if let Some((_, address)) = self.account {
+            out[tx..tx + 32].copy_from_slice(&address.to_be_bytes());
+            tx += 32;
+        }
+
         (tx, Error::Success as _)
     }
 
@@ -158,3 +209,41 @@ impl Viewable for AddrUI {
         (0, Error::CommandNotAllowed as _)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_error_code,
+        constants::{
+            instructions::{CLA, INS_GET_PUBLIC_KEY},
+            ApduError,
+        },
+        handle_apdu_raw,
+    };
+    use std::convert::TryInto;
+
+    const H: u32 = 0x8000_0000;
+
+    fn apdu(path: &[u32]) -> std::vec::Vec<u8> {
+        let len = 1 + 4 * path.len();
+        let mut apdu = std::vec![CLA, INS_GET_PUBLIC_KEY, 0, 0, len as u8, path.len() as u8];
+        for c in path {
+            apdu.extend_from_slice(&c.to_be_bytes());
+        }
+
+        apdu
+    }
+
+    #[test]
+    fn refuses_non_eip2645_path() {
+        let (_, tx, out) = handle_apdu_raw(&apdu(&[44 | H, 9004 | H, H, H, H, 0]));
+        assert_error_code!(tx, out, ApduError::DataInvalid);
+    }
+
+    #[test]
+    fn refuses_too_deep_path() {
+        let path = [2645 | H, 1195502025 | H, 1148870696 | H, H, H, 0, 0];
+        let (_, tx, out) = handle_apdu_raw(&apdu(&path));
+        assert_error_code!(tx, out, ApduError::DataInvalid);
+    }
+}
@@ -0,0 +1,130 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use arrayref::array_ref;
+
+use crate::{
+    constants::ApduError as Error,
+    crypto::{self, Signature},
+    dispatcher::ApduHandler,
+    sys,
+    utils::ApduBufferRead,
+};
+
+/// Recovers the public key that made a signature, as returned by the signing instructions
+///
+/// No key of the device is involved, so no confirmation is needed
+pub struct RecoverPublicKey;
+
+impl RecoverPublicKey {
+    /// Hash followed by the R S V signature
+    pub const PAYLOAD_LEN: usize = 32 + Signature::LEN;
+}
+
+impl ApduHandler for RecoverPublicKey {
+    #[inline(never)]
+    fn handle<'apdu>(
+        _: &mut u32,
+        tx: &mut u32,
+        buffer: ApduBufferRead<'apdu>,
+    ) -> Result<(), Error> {
+        sys::zemu_log_stack("RecoverPublicKey::handle\x00");
+
+        *tx = 0;
+
+        let cdata = buffer.payload().map_err(|_| Error::DataInvalid)?;
+        if cdata.len() != Self::PAYLOAD_LEN {
+            return Err(Error::DataInvalid);
+        }
+
+        let hash = array_ref![cdata, 0, 32];
+        let signature =
+            Signature::from_rsv(array_ref![cdata, 32, Signature::LEN]).ok_or(Error::DataInvalid)?;
+
+        let public_key = crypto::recover(hash, &signature.r, &signature.s, signature.v)
+            .ok_or(Error::DataInvalid)?;
+
+        buffer.write()[..32].copy_from_slice(&public_key);
+        *tx = 32;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_error_code,
+        constants::{
+            instructions::{CLA, INS_RECOVER_PUBLIC_KEY},
+            ApduError,
+        },
+        crypto::{sign, Scalar},
+        handle_apdu_raw,
+    };
+    use std::convert::TryInto;
+
+    fn apdu(hash: &[u8; 32], rsv: &[u8]) -> std::vec::Vec<u8> {
+        let mut apdu = std::vec![CLA, INS_RECOVER_PUBLIC_KEY, 0, 0, (32 + rsv.len()) as u8];
+        apdu.extend_from_slice(hash);
+        apdu.extend_from_slice(rsv);
+
+        apdu
+    }
+
+    #[test]
+    fn recovers_signer() {
+        let mut key = [0; 32];
+        key[31] = 1;
+        let mut hash = [0; 32];
+        hash[31] = 1;
+
+        //as returned by SignFeltUI::accept
+        let signature = sign(&Scalar::from_be_bytes(&key).unwrap(), &hash, None)
+            .unwrap()
+            .normalize()
+            .unwrap();
+
+        let (_, tx, out) = handle_apdu_raw(&apdu(&hash, &signature.to_rsv()));
+        assert_eq!(tx, 34);
+        assert_error_code!(tx, out, ApduError::Success);
+        //the public key of 1 is the generator
+        assert_eq!(
+            hex::encode(&out[..32]),
+            "01ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca"
+        );
+    }
+
+    #[test]
+    fn refuses_malformed_requests() {
+        let hash = [1; 32];
+
+        let (_, tx, out) = handle_apdu_raw(&apdu(&hash, &[0; 64]));
+        assert_error_code!(tx, out, ApduError::DataInvalid);
+
+        //invalid V
+        let mut rsv = [1; Signature::LEN];
+        rsv[64] = 2;
+        let (_, tx, out) = handle_apdu_raw(&apdu(&hash, &rsv));
+        assert_error_code!(tx, out, ApduError::DataInvalid);
+
+        //R = 0
+        rsv = [0; Signature::LEN];
+        rsv[63] = 1;
+        let (_, tx, out) = handle_apdu_raw(&apdu(&hash, &rsv));
+        assert_error_code!(tx, out, ApduError::DataInvalid);
+    }
+}
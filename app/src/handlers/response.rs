@@ -0,0 +1,96 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use crate::{
+    constants::ApduError as Error,
+    dispatcher::ApduHandler,
+    sys,
+    utils::{ApduBufferRead, Response},
+};
+
+/// Returns the next chunk of a response queued by another instruction
+///
+/// The status word is `MoreDataAvailable` until the last chunk
+pub struct GetNextChunk;
+
+impl ApduHandler for GetNextChunk {
+    #[inline(never)]
+    fn handle<'apdu>(
+        _: &mut u32,
+        tx: &mut u32,
+        buffer: ApduBufferRead<'apdu>,
+    ) -> Result<(), Error> {
+        sys::zemu_log_stack("GetNextChunk::handle\x00");
+
+        *tx = 0;
+
+        let (len, status) = Response::next_chunk(buffer.write())?;
+        *tx = len as u32;
+
+        match status {
+            Error::Success => Ok(()),
+            more => Err(more),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        assert_error_code,
+        constants::{
+            instructions::{CLA, INS_GET_NEXT_CHUNK},
+            ApduError,
+        },
+        handle_apdu_raw,
+        utils::Response,
+    };
+    use std::convert::TryInto;
+
+    fn next_chunk() -> (u32, std::vec::Vec<u8>) {
+        let (_, tx, out) = handle_apdu_raw(&[CLA, INS_GET_NEXT_CHUNK, 0, 0, 0]);
+        (tx, out)
+    }
+
+    #[test]
+    fn reads_queued_response() {
+        let data: std::vec::Vec<u8> = (0..600u32).map(|i| i as u8).collect();
+
+        Response::start().unwrap();
+        Response::push(&data[..100]).unwrap();
+        Response::push(&data[100..]).unwrap();
+
+        let mut read = std::vec::Vec::new();
+        loop {
+            let (tx, out) = next_chunk();
+            let len = tx as usize - 2;
+            assert!(len <= Response::CHUNK_LEN);
+            read.extend_from_slice(&out[..len]);
+
+            if read.len() < data.len() {
+                assert_error_code!(tx, out, ApduError::MoreDataAvailable);
+            } else {
+                assert_error_code!(tx, out, ApduError::Success);
+                break;
+            }
+        }
+        assert_eq!(read, data);
+
+        //the response was discarded after the last chunk
+        let (tx, out) = next_chunk();
+        assert_eq!(tx, 2);
+        assert_error_code!(tx, out, ApduError::ApduCodeEmptyBuffer);
+    }
+}
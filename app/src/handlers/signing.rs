@@ -13,7 +13,6 @@
 *  See the License for the specific language governing permissions and
 *  limitations under the License.
 ********************************************************************************/
-use arrayref::array_mut_ref;
 use bolos::{
     crypto::bip32::BIP32Path,
     hash::{Hasher, Sha256},
@@ -23,39 +22,48 @@ use zemu_sys::{Show, ViewError, Viewable};
 
 use crate::{
     constants::{ApduError as Error, BIP32_MAX_LENGTH},
-    crypto::Curve,
     dispatcher::ApduHandler,
-    handlers::{convert_der_to_rs, handle_ui_message, verify_bip32_path},
+    handlers::{handle_ui_message, verify_bip32_path},
+    parser::DisplayableItem,
+    path::PathItems,
     sys,
     utils::{hex_encode, ApduBufferRead, ApduPanic, Uploader},
 };
 
+mod declare;
+pub use declare::{SignDeclare, SignDeclareV3};
+
+mod deploy_account;
+pub use deploy_account::{SignDeployAccount, SignDeployAccountV3};
+
 mod felt;
 pub use felt::SignFelt;
 
+mod felt_batch;
+pub use felt_batch::SignFeltBatch;
+
+mod invoke;
+pub use invoke::{SignInvoke, SignInvokeV3};
+
+mod starkex;
+pub use starkex::{SignStarkExOrder, SignStarkExTransfer};
+
+mod transaction;
+
+mod typed_data;
+pub use typed_data::SignTypedData;
+
 mod blind_sign_toggle;
+pub(crate) use blind_sign_toggle::blind_sign_enabled;
+
+mod testnet_toggle;
+pub(crate) use testnet_toggle::testnet_sign_enabled;
 
 pub struct Sign;
 
 impl Sign {
     pub const SIGN_HASH_SIZE: usize = 32;
 
-    //(actual_size, [u8; MAX_SIGNATURE_SIZE])
-    #[inline(never)]
-    pub fn sign<const LEN: usize>(
-        path: &BIP32Path<LEN>,
-        data: &[u8],
-    ) -> Result<(bool, usize, [u8; 100]), Error> {
-        let sk = Curve::Stark256.to_secret(path);
-
-        let mut out = [0; 100];
-        let (parity, sz) = sk
-            .sign(data, &mut out[..])
-            .map_err(|_| Error::ExecutionError)?;
-
-        Ok((parity, sz, out))
-    }
-
     #[inline(never)]
     fn sha256_digest(buffer: &[u8]) -> Result<[u8; Self::SIGN_HASH_SIZE], Error> {
         Sha256::digest(buffer).map_err(|_| Error::ExecutionError)
@@ -115,7 +123,7 @@ pub(crate) struct SignUI<const B: usize> {
 
 impl<const B: usize> Viewable for SignUI<B> {
     fn num_items(&mut self) -> Result<u8, ViewError> {
-        Ok(1)
+        Ok(1 + PathItems::new(self.path.components()).num_items() as u8)
     }
 
     #[inline(never)]
@@ -126,7 +134,13 @@ impl<const B: usize> Viewable for SignUI<B> {
         message: &mut [u8],
         page: u8,
     ) -> Result<u8, ViewError> {
-        match item_n {
+        let path_items = PathItems::new(self.path.components());
+        let n_path = path_items.num_items() as u8;
+        if item_n < n_path {
+            return path_items.render_item(item_n, title, message, page);
+        }
+
+        match item_n - n_path {
             0 => {
                 let title_content = pic_str!(b"Sign");
                 title[..title_content.len()].copy_from_slice(title_content);
@@ -142,37 +156,11 @@ impl<const B: usize> Viewable for SignUI<B> {
     }
 
     fn accept(&mut self, out: &mut [u8]) -> (usize, u16) {
-        let (parity, sig_len, sig) = match Sign::sign(&self.path, &self.hash[..]) {
+        let mut tx = match SignFelt::sign_rsv(&self.path, &self.hash, out) {
             Err(e) => return (0, e as _),
-            Ok(k) => k,
+            Ok(tx) => tx,
         };
 
-        let mut tx = 0;
-
-        {
-            //local copy of the mutable slice
-            let out = &mut *out;
-
-            //create 2 mut subslices (r, s) that are 32 byte long
-            // and are referencing out
-            let (r, out) = out.split_at_mut(32);
-            let r = array_mut_ref![r, 0, 32];
-            tx += 32;
-
-            //no need to save the second part of the split
-            let (s, _) = out.split_at_mut(32);
-            let s = array_mut_ref![s, 0, 32];
-            tx += 32;
-
-            //write as R S V
-            if convert_der_to_rs(&sig[..sig_len], r, s).is_err() {
-                return (0, Error::ExecutionError as _);
-            }
-        }
-
-        out[tx] = parity as u8;
-        tx += 1;
-
         //write unsigned_hash to buffer
         out[tx..tx + Sign::SIGN_HASH_SIZE].copy_from_slice(&self.hash[..]);
         tx += Sign::SIGN_HASH_SIZE;
@@ -0,0 +1,54 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use crate::{
+    constants::ApduError as Error,
+    dispatcher::ApduHandler,
+    parser::{DeployAccountV1, DeployAccountV3},
+    sys,
+    utils::ApduBufferRead,
+};
+
+use super::transaction::handle_tx;
+
+pub struct SignDeployAccount;
+
+impl ApduHandler for SignDeployAccount {
+    #[inline(never)]
+    fn handle<'apdu>(
+        flags: &mut u32,
+        tx: &mut u32,
+        buffer: ApduBufferRead<'apdu>,
+    ) -> Result<(), Error> {
+        sys::zemu_log_stack("SignDeployAccount::handle\x00");
+
+        handle_tx::<DeployAccountV1>(Self, flags, tx, &buffer)
+    }
+}
+
+pub struct SignDeployAccountV3;
+
+impl ApduHandler for SignDeployAccountV3 {
+    #[inline(never)]
+    fn handle<'apdu>(
+        flags: &mut u32,
+        tx: &mut u32,
+        buffer: ApduBufferRead<'apdu>,
+    ) -> Result<(), Error> {
+        sys::zemu_log_stack("SignDeployAccountV3::handle\x00");
+
+        handle_tx::<DeployAccountV3>(Self, flags, tx, &buffer)
+    }
+}
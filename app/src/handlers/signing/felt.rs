@@ -17,15 +17,15 @@ use bolos::{crypto::bip32::BIP32Path, pic_str, PIC};
 use core::convert::TryFrom;
 use zemu_sys::{Show, ViewError, Viewable};
 
-use arrayref::array_mut_ref;
-
 use crate::{
     constants::{ApduError as Error, BIP32_MAX_LENGTH},
-    crypto::Curve,
+    crypto::{verify, AffinePoint, Curve, Signature},
     dispatcher::ApduHandler,
-    handlers::{convert_der_to_rs, handle_ui_message, verify_bip32_path},
+    handlers::verify_bip32_path,
+    parser::{render_felt, DisplayableItem},
+    path::{is_unusual, PathItems},
     sys,
-    utils::{hex_encode, ApduBufferRead, ApduPanic, Uploader},
+    utils::{ApduBufferRead, Uploader},
 };
 
 pub struct SignFelt;
@@ -33,30 +33,63 @@ pub struct SignFelt;
 impl SignFelt {
     pub const SIGN_ITEM_SIZE: usize = 32;
 
-    //(actual_size, [u8; MAX_SIGNATURE_SIZE])
+    /// Signs `felt` and writes the normalized signature to `out` as R S V
+    ///
+    /// Returns the number of bytes written
     #[inline(never)]
-    pub fn sign<const LEN: usize>(
+    pub fn sign_rsv<const LEN: usize>(
         path: &BIP32Path<LEN>,
-        data: &[u8],
-    ) -> Result<(bool, usize, [u8; 100]), Error> {
-        let mut out = [0; 100];
+        felt: &[u8; Self::SIGN_ITEM_SIZE],
+        out: &mut [u8],
+    ) -> Result<usize, Error> {
+        let pkey = Self::public_key(path)?;
 
-        let (parity, sz) = Self::sign_into(path, data, &mut out)?;
+        Self::sign_rsv_with(path, &pkey, felt, out)
+    }
 
-        Ok((parity, sz, out))
+    /// Derives the public key of `path`, which signatures are verified against
+    #[inline(never)]
+    pub fn public_key<const LEN: usize>(path: &BIP32Path<LEN>) -> Result<AffinePoint, Error> {
+        Curve::Stark256
+            .to_secret(path)
+            .into_public()
+            .ok()
+            .and_then(|pkey| pkey.point())
+            .ok_or(Error::ExecutionError)
     }
 
+    /// Same as [`Self::sign_rsv`], with `pkey` the public key of `path`
+    ///
+    /// Signing several items with the same `path` derives `pkey` only once
     #[inline(never)]
-    pub fn sign_into<const LEN: usize>(
+    pub fn sign_rsv_with<const LEN: usize>(
         path: &BIP32Path<LEN>,
-        data: &[u8],
-        out: &mut [u8; 100],
-    ) -> Result<(bool, usize), Error> {
-        let sk = Curve::Stark256.to_secret(path);
+        pkey: &AffinePoint,
+        felt: &[u8; Self::SIGN_ITEM_SIZE],
+        out: &mut [u8],
+    ) -> Result<usize, Error> {
+        let out = out
+            .get_mut(..Signature::LEN)
+            .ok_or(Error::OutputBufferTooSmall)?;
+
+        //deterministic, the same felt always gets the same signature
+        let signature = Curve::Stark256
+            .to_secret(path)
+            .sign(felt, None)
+            .map_err(|_| Error::ExecutionError)?;
+
+        //V is the parity of the y coordinate of R, flipped along with S
+        let signature = signature.normalize().ok_or(Error::ExecutionError)?;
+
+        //never release a signature that doesn't verify, a faulty one could leak the key
+        if !verify(pkey, felt, &signature.r, &signature.s) {
+            return Err(Error::SignVerifyError);
+        }
 
-        let (parity, sz) = sk.sign(data, out).map_err(|_| Error::ExecutionError)?;
+        //write as R S V
+        out.copy_from_slice(&signature.to_rsv());
 
-        Ok((parity, sz))
+        Ok(Signature::LEN)
     }
 }
 
@@ -77,12 +110,13 @@ impl ApduHandler for SignFelt {
         }
 
         if let Some(upload) = Uploader::new(Self).upload(&buffer)? {
-            let req_confirmation = upload.p2 >= 1;
-
             let path = BIP32Path::<BIP32_MAX_LENGTH>::read(upload.first)
                 .map_err(|_| Error::DataInvalid)?;
             verify_bip32_path(&path)?;
 
+            //unusual paths are never signed without the user seeing them
+            let req_confirmation = upload.p2 >= 1 || is_unusual(path.components());
+
             if upload.data.len() != 32 {
                 return Err(Error::DataInvalid);
             }
@@ -130,7 +164,7 @@ pub(crate) struct SignFeltUI<const B: usize> {
 
 impl<const B: usize> Viewable for SignFeltUI<B> {
     fn num_items(&mut self) -> Result<u8, ViewError> {
-        Ok(1)
+        Ok(1 + PathItems::new(self.path.components()).num_items() as u8)
     }
 
     #[inline(never)]
@@ -141,57 +175,112 @@ impl<const B: usize> Viewable for SignFeltUI<B> {
         message: &mut [u8],
         page: u8,
     ) -> Result<u8, ViewError> {
-        match item_n {
+        let path_items = PathItems::new(self.path.components());
+        let n_path = path_items.num_items() as u8;
+        if item_n < n_path {
+            return path_items.render_item(item_n, title, message, page);
+        }
+
+        match item_n - n_path {
             0 => {
                 let title_content = pic_str!(b"Sign Felt");
                 title[..title_content.len()].copy_from_slice(title_content);
 
-                let mut hex_buf = [0; SignFelt::SIGN_ITEM_SIZE * 2];
-                //this is impossible that will error since the sizes are all checked
-                let len = hex_encode(&self.felt[..], &mut hex_buf).apdu_unwrap();
-
-                handle_ui_message(&hex_buf[..len], message, page)
+                render_felt(&self.felt, message, page)
             }
             _ => Err(ViewError::NoData),
         }
     }
 
     fn accept(&mut self, out: &mut [u8]) -> (usize, u16) {
-        let (parity, sig_len, sig) = match SignFelt::sign(&self.path, &self.felt[..]) {
-            Err(e) => return (0, e as _),
-            Ok(k) => k,
-        };
-
-        let mut tx = 0;
-
-        {
-            //local copy of the mutable slice
-            let out = &mut *out;
-
-            //create 2 mut subslices (r, s) that are 32 byte long
-            // and are referencing out
-            let (r, out) = out.split_at_mut(32);
-            let r = array_mut_ref![r, 0, 32];
-            tx += 32;
-
-            //no need to save the second part of the split
-            let (s, _) = out.split_at_mut(32);
-            let s = array_mut_ref![s, 0, 32];
-            tx += 32;
-
-            //write as R S V
-            if convert_der_to_rs(&sig[..sig_len], r, s).is_err() {
-                return (0, Error::ExecutionError as _);
-            }
+        match SignFelt::sign_rsv(&self.path, &self.felt, out) {
+            Err(e) => (0, e as _),
+            Ok(tx) => (tx, Error::Success as _),
         }
-
-        out[tx] = parity as u8;
-        tx += 1;
-
-        (tx, Error::Success as _)
     }
 
     fn reject(&mut self, _: &mut [u8]) -> (usize, u16) {
         (0, Error::CommandNotAllowed as _)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{public_key, Scalar};
+    use starknet_crypto::FieldElement;
+
+    const H: u32 = 0x8000_0000;
+
+    /// Standard path with the given index
+    fn path(index: u32) -> BIP32Path<BIP32_MAX_LENGTH> {
+        let mut bytes = std::vec![6];
+        for c in [2645 | H, 1195502025 | H, 1148870696 | H, H, H, index].iter() {
+            bytes.extend_from_slice(&c.to_be_bytes());
+        }
+
+        BIP32Path::read(&bytes).ok().unwrap()
+    }
+
+    fn private_key(path: &BIP32Path<BIP32_MAX_LENGTH>) -> Scalar {
+        Curve::Stark256.to_secret(path).private_key().ok().unwrap()
+    }
+
+    fn field_element(bytes: &[u8; 32]) -> FieldElement {
+        FieldElement::from_bytes_be(bytes).unwrap()
+    }
+
+    #[test]
+    fn signs_like_starknet_crypto() {
+        let mut felts = [[0; 32]; 3];
+        felts[0][31] = 1;
+        felts[1].copy_from_slice(
+            &hex::decode("0397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f")
+                .unwrap(),
+        );
+        felts[2] = [0xff; 32];
+        felts[2][0] = 0x07;
+
+        for index in 0..4 {
+            let path = path(index);
+            let key = private_key(&path);
+            let pkey = public_key(&key).unwrap();
+            let sk = field_element(&key.to_be_bytes());
+            assert_eq!(
+                pkey.x.to_be_bytes(),
+                starknet_crypto::get_public_key(&sk).to_bytes_be()
+            );
+
+            for felt in felts.iter() {
+                let mut out = [0; Signature::LEN];
+                let len = SignFelt::sign_rsv_with(&path, &pkey, felt, &mut out).unwrap();
+                assert_eq!(len, Signature::LEN);
+
+                let hash = field_element(felt);
+                let k = starknet_crypto::rfc6979_generate_k(&hash, &sk, None);
+                let expected = starknet_crypto::sign(&sk, &hash, &k).unwrap();
+                let expected = Signature {
+                    r: expected.r.to_bytes_be(),
+                    s: expected.s.to_bytes_be(),
+                    v: (expected.v == FieldElement::ONE) as u8,
+                };
+                assert_eq!(out, expected.normalize().unwrap().to_rsv());
+
+                //deterministic
+                let mut again = [0; Signature::LEN];
+                SignFelt::sign_rsv_with(&path, &pkey, felt, &mut again).unwrap();
+                assert_eq!(out, again);
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_signatures_which_dont_verify() {
+        let other_pkey = public_key(&private_key(&path(1))).unwrap();
+
+        let mut out = [0; Signature::LEN];
+        let result = SignFelt::sign_rsv_with(&path(0), &other_pkey, &[1; 32], &mut out);
+        assert_eq!(result, Err(Error::SignVerifyError));
+        assert_eq!(out, [0; Signature::LEN]);
+    }
+}
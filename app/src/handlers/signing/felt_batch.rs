@@ -0,0 +1,186 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use bolos::{crypto::bip32::BIP32Path, pic_str, PIC};
+use zemu_sys::{Show, ViewError, Viewable};
+
+use crate::{
+    constants::{ApduError as Error, BIP32_MAX_LENGTH},
+    crypto::Signature,
+    dispatcher::ApduHandler,
+    handlers::{handle_ui_message, verify_bip32_path},
+    parser::{felts_iter, render_felt, render_title, DisplayableItem, FeltReader, TextBuffer},
+    path::PathItems,
+    sys,
+    utils::{ApduBufferRead, Response, Uploader},
+};
+
+use super::SignFelt;
+
+/// Signs several felts behind a single review
+///
+/// The signatures are queued as a [`Response`], read with `INS_GET_NEXT_CHUNK`
+pub struct SignFeltBatch;
+
+impl SignFeltBatch {
+    /// Maximum number of felts signed at once
+    pub const MAX_FELTS: usize = 8;
+
+    /// Signs all the `felts`, queueing the signatures,
+    /// and writes the first chunk to `out`
+    ///
+    /// Returns the number of bytes written and the status word to send
+    #[inline(never)]
+    fn sign_all<const B: usize>(
+        path: &BIP32Path<B>,
+        felts: &[[u8; SignFelt::SIGN_ITEM_SIZE]],
+        out: &mut [u8],
+    ) -> Result<(usize, Error), Error> {
+        Response::start()?;
+
+        //derived once, every signature is verified against it
+        let pkey = SignFelt::public_key(path)?;
+        for felt in felts {
+            let mut rsv = [0; Signature::LEN];
+            SignFelt::sign_rsv_with(path, &pkey, felt, &mut rsv)?;
+
+            Response::push(&rsv)?;
+        }
+
+        Response::next_chunk(out)
+    }
+}
+
+impl ApduHandler for SignFeltBatch {
+    #[inline(never)]
+    fn handle<'apdu>(
+        flags: &mut u32,
+        tx: &mut u32,
+        buffer: ApduBufferRead<'apdu>,
+    ) -> Result<(), Error> {
+        sys::zemu_log_stack("SignFeltBatch::handle\x00");
+
+        *tx = 0;
+
+        if !super::blind_sign_toggle::blind_sign_enabled() {
+            sys::zemu_log_stack("blind_signing disabled\x00");
+            return Err(Error::ApduCodeConditionsNotSatisfied);
+        }
+
+        if let Some(upload) = Uploader::new(Self).upload(&buffer)? {
+            let path = BIP32Path::<BIP32_MAX_LENGTH>::read(upload.first)
+                .map_err(|_| Error::DataInvalid)?;
+            verify_bip32_path(&path)?;
+
+            let n = upload.data.len() / SignFelt::SIGN_ITEM_SIZE;
+            if n == 0 || n > Self::MAX_FELTS {
+                return Err(Error::DataInvalid);
+            }
+
+            let mut reader = FeltReader::new(upload.data);
+            let data = reader.felts(n)?;
+            reader.finish()?;
+
+            //copied, so `BUFFER` is released once `upload` is dropped
+            let mut felts = [[0; SignFelt::SIGN_ITEM_SIZE]; Self::MAX_FELTS];
+            for (felt, item) in felts.iter_mut().zip(felts_iter(data)) {
+                *felt = *item;
+            }
+
+            let ui = SignFeltBatchUI { path, felts, n };
+
+            //always reviewed, whatever P2
+            unsafe { ui.show(flags) }
+                .map(|_| ())
+                .map_err(|_| Error::ExecutionError)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) struct SignFeltBatchUI<const B: usize> {
+    path: BIP32Path<B>,
+    felts: [[u8; SignFelt::SIGN_ITEM_SIZE]; SignFeltBatch::MAX_FELTS],
+    n: usize,
+}
+
+impl<const B: usize> SignFeltBatchUI<B> {
+    fn felts(&self) -> &[[u8; SignFelt::SIGN_ITEM_SIZE]] {
+        &self.felts[..self.n]
+    }
+}
+
+impl<const B: usize> Viewable for SignFeltBatchUI<B> {
+    fn num_items(&mut self) -> Result<u8, ViewError> {
+        let n_path = PathItems::new(self.path.components()).num_items();
+
+        //at most MAX_FELTS felts
+        Ok((n_path + 1 + self.n) as u8)
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &mut self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        let path_items = PathItems::new(self.path.components());
+        let n_path = path_items.num_items() as u8;
+        if item_n < n_path {
+            return path_items.render_item(item_n, title, message, page);
+        }
+
+        let n_felts = self.n;
+        match (item_n - n_path) as usize {
+            0 => {
+                render_title(pic_str!(b"Sign Felts"), title);
+
+                let mut text = TextBuffer::<4>::new();
+                text.push_u64(n_felts as u64);
+                handle_ui_message(text.as_bytes(), message, page)
+            }
+            n => {
+                let i = n - 1;
+                let felt = self.felts().get(i).ok_or(ViewError::NoData)?;
+
+                let mut text = TextBuffer::<16>::new();
+                text.push(pic_str!(b"Felt "))
+                    .push_u64(i as u64 + 1)
+                    .push(pic_str!(b"/"))
+                    .push_u64(n_felts as u64);
+                render_title(text.as_bytes(), title);
+
+                render_felt(felt, message, page)
+            }
+        }
+    }
+
+    fn accept(&mut self, out: &mut [u8]) -> (usize, u16) {
+        match SignFeltBatch::sign_all(&self.path, self.felts(), out) {
+            Ok((tx, status)) => (tx, status as _),
+            Err(e) => {
+                Response::discard();
+                (0, e as _)
+            }
+        }
+    }
+
+    fn reject(&mut self, _: &mut [u8]) -> (usize, u16) {
+        (0, Error::CommandNotAllowed as _)
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    constants::ApduError as Error,
+    parser::{Network, FELT_SIZE},
+    sys,
+};
+
+#[cfg(feature = "testnet-toggle")]
+mod impls {
+    #[repr(C)]
+    pub struct TestnetSignToggle {
+        pub toggle: bool,
+        pub message: [i8; 9],
+    }
+
+    cfg_if::cfg_if! {
+        if #[cfg(any(unix, windows))] {
+            /// Provide a mock for tests
+            #[allow(non_upper_case_globals)]
+            pub static mut testnet_sign: TestnetSignToggle = TestnetSignToggle {
+                toggle: false,
+                message: [0; 9],
+            };
+        } else {
+            extern "C" {
+                ///Link to the C code
+                pub static mut testnet_sign: TestnetSignToggle;
+            }
+        }
+    }
+}
+
+/// Returns if signing for networks other than mainnet is enabled in this execution
+pub fn testnet_sign_enabled() -> bool {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "testnet-toggle")] {
+            //safe: guaranteed no data races
+            unsafe { impls::testnet_sign.toggle }
+        } else {
+            true
+        }
+    }
+}
+
+/// Verifies signing for the network identified by `chain_id` is allowed:
+/// anything but mainnet requires testnet signing to be enabled
+pub fn verify_network(chain_id: &[u8; FELT_SIZE]) -> Result<(), Error> {
+    if Network::from_chain_id(chain_id) == Some(Network::Mainnet) || testnet_sign_enabled() {
+        Ok(())
+    } else {
+        sys::zemu_log_stack("testnet signing disabled\x00");
+        Err(Error::ApduCodeConditionsNotSatisfied)
+    }
+}
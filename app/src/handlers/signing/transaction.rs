@@ -0,0 +1,267 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use bolos::crypto::bip32::BIP32Path;
+use zemu_sys::{Show, ViewError, Viewable};
+
+use crate::{
+    constants::{ApduError as Error, BIP32_MAX_LENGTH},
+    handlers::{resources::BUFFERAccessors, verify_bip32_path},
+    parser::{
+        DeclareV2, DeclareV3, DeployAccountV1, DeployAccountV3, DisplayableItem, InvokeV1,
+        InvokeV3, StarkExLimitOrder, StarkExTransfer, TypedData, FELT_SIZE,
+    },
+    path::PathItems,
+    sys,
+    utils::{ApduBufferRead, Uploader},
+};
+
+use super::{blind_sign_toggle::blind_sign_enabled, testnet_toggle::verify_network, SignFelt};
+
+/// P2 of the first packet of v3 transactions which include L1 data gas bounds
+const P2_L1_DATA_GAS: u8 = 0x01;
+
+/// Returns if the v3 transaction uploaded with `p2` includes L1 data gas bounds
+fn with_l1_data_gas(p2: u8) -> Result<bool, Error> {
+    match p2 {
+        0 => Ok(false),
+        P2_L1_DATA_GAS => Ok(true),
+        _ => Err(Error::InvalidP1P2),
+    }
+}
+
+/// A transaction (or message) reviewed on device and signed by its hash
+pub(super) trait Transaction<'b>: DisplayableItem + Sized {
+    /// Parses the transaction uploaded with `p2` in its first packet
+    fn from_upload(data: &'b [u8], p2: u8) -> Result<Self, Error>;
+
+    /// The chain id the transaction is bound to, if any
+    fn chain_id(&self) -> Option<&'b [u8; FELT_SIZE]>;
+
+    /// Returns if parts of the transaction can't be shown to the user
+    fn is_blind(&self) -> bool {
+        false
+    }
+
+    fn signed_hash(&self) -> [u8; FELT_SIZE];
+}
+
+macro_rules! impl_transaction {
+    ($tx:ident, |$data:ident, $p2:ident| $parse:expr, |$this:ident| $chain_id:expr) => {
+        impl<'b> Transaction<'b> for $tx<'b> {
+            fn from_upload($data: &'b [u8], $p2: u8) -> Result<Self, Error> {
+                Ok($parse?)
+            }
+
+            fn chain_id(&self) -> Option<&'b [u8; FELT_SIZE]> {
+                let $this = self;
+                $chain_id
+            }
+
+            fn signed_hash(&self) -> [u8; FELT_SIZE] {
+                self.hash().to_be_bytes()
+            }
+        }
+    };
+}
+
+impl_transaction!(InvokeV1, |data, _p2| InvokeV1::parse(data), |tx| Some(
+    tx.chain_id
+));
+impl_transaction!(
+    InvokeV3,
+    |data, p2| InvokeV3::parse(data, with_l1_data_gas(p2)?),
+    |tx| Some(tx.common.chain_id)
+);
+impl_transaction!(DeclareV2, |data, _p2| DeclareV2::parse(data), |tx| Some(
+    tx.chain_id
+));
+impl_transaction!(
+    DeclareV3,
+    |data, p2| DeclareV3::parse(data, with_l1_data_gas(p2)?),
+    |tx| Some(tx.common.chain_id)
+);
+impl_transaction!(
+    DeployAccountV1,
+    |data, _p2| DeployAccountV1::parse(data),
+    |tx| Some(tx.chain_id)
+);
+impl_transaction!(
+    DeployAccountV3,
+    |data, p2| DeployAccountV3::parse(data, with_l1_data_gas(p2)?),
+    |tx| Some(tx.common.chain_id)
+);
+impl_transaction!(
+    StarkExLimitOrder,
+    |data, _p2| StarkExLimitOrder::parse(data),
+    |_tx| None
+);
+impl_transaction!(
+    StarkExTransfer,
+    |data, _p2| StarkExTransfer::parse(data),
+    |_tx| None
+);
+
+impl<'b> Transaction<'b> for TypedData<'b> {
+    fn from_upload(data: &'b [u8], _: u8) -> Result<Self, Error> {
+        Ok(TypedData::parse(data)?)
+    }
+
+    fn chain_id(&self) -> Option<&'b [u8; FELT_SIZE]> {
+        Some(self.chain_id)
+    }
+
+    fn is_blind(&self) -> bool {
+        //fields the device can't show are blind signed
+        self.has_unverified_fields()
+    }
+
+    fn signed_hash(&self) -> [u8; FELT_SIZE] {
+        self.hash().to_be_bytes()
+    }
+}
+
+/// Uploads a transaction, then shows it for review once complete
+///
+/// The UI reads the transaction from `BUFFER`, which stays pinned until the review is over
+#[inline(never)]
+pub(super) fn handle_tx<T: Transaction<'static>>(
+    accessor: impl Into<BUFFERAccessors>,
+    flags: &mut u32,
+    tx: &mut u32,
+    buffer: &ApduBufferRead<'_>,
+) -> Result<(), Error> {
+    let accessor = accessor.into();
+    *tx = 0;
+
+    if let Some(mut upload) = Uploader::new(accessor).upload(buffer)? {
+        let path =
+            BIP32Path::<BIP32_MAX_LENGTH>::read(upload.first).map_err(|_| Error::DataInvalid)?;
+        verify_bip32_path(&path)?;
+
+        let transaction = T::from_upload(upload.data, upload.p2)?;
+        if let Some(chain_id) = transaction.chain_id() {
+            verify_network(chain_id)?;
+        }
+        if transaction.is_blind() && !blind_sign_enabled() {
+            sys::zemu_log_stack("blind_signing disabled\x00");
+            return Err(Error::ApduCodeConditionsNotSatisfied);
+        }
+        let hash = transaction.signed_hash();
+
+        upload.persist()?;
+        *tx = TransactionUI::new(accessor, path, hash, transaction).review(flags)?;
+    }
+
+    Ok(())
+}
+
+/// UI to review a transaction parsed from `BUFFER`
+///
+/// The hash is computed on device beforehand and is what gets signed on approval
+pub(crate) struct TransactionUI<T, const B: usize> {
+    accessor: BUFFERAccessors,
+    path: BIP32Path<B>,
+    hash: [u8; SignFelt::SIGN_ITEM_SIZE],
+    tx: T,
+}
+
+impl<T: DisplayableItem, const B: usize> TransactionUI<T, B> {
+    pub fn new(
+        accessor: impl Into<BUFFERAccessors>,
+        path: BIP32Path<B>,
+        hash: [u8; SignFelt::SIGN_ITEM_SIZE],
+        tx: T,
+    ) -> Self {
+        Self {
+            accessor: accessor.into(),
+            path,
+            hash,
+            tx,
+        }
+    }
+
+    /// Show the transaction for review
+    ///
+    /// `BUFFER` is expected to be kept locked by `accessor`, and is released
+    /// once the user has approved or rejected the transaction
+    #[inline(never)]
+    pub fn review(self, flags: &mut u32) -> Result<u32, Error> {
+        let accessor = self.accessor;
+
+        let result = if self.num_items_total() > u8::MAX as usize {
+            Err(Error::DataInvalid)
+        } else {
+            unsafe { self.show(flags) }
+                .map_err(|_| Error::ExecutionError)
+                .map(|_| 0)
+        };
+
+        if result.is_err() {
+            Uploader::release(accessor);
+        }
+
+        result
+    }
+
+    fn num_items_total(&self) -> usize {
+        PathItems::new(self.path.components()).num_items() + self.tx.num_items()
+    }
+}
+
+impl<T: DisplayableItem, const B: usize> Viewable for TransactionUI<T, B> {
+    fn num_items(&mut self) -> Result<u8, ViewError> {
+        //checked before showing
+        Ok(self.num_items_total() as u8)
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &mut self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        let path_items = PathItems::new(self.path.components());
+        let n_path = path_items.num_items() as u8;
+        if item_n < n_path {
+            return path_items.render_item(item_n, title, message, page);
+        }
+
+        self.tx.render_item(item_n - n_path, title, message, page)
+    }
+
+    fn accept(&mut self, out: &mut [u8]) -> (usize, u16) {
+        Uploader::release(self.accessor);
+
+        let mut tx = match SignFelt::sign_rsv(&self.path, &self.hash, out) {
+            Err(e) => return (0, e as _),
+            Ok(tx) => tx,
+        };
+
+        //write the computed hash to buffer
+        out[tx..tx + SignFelt::SIGN_ITEM_SIZE].copy_from_slice(&self.hash[..]);
+        tx += SignFelt::SIGN_ITEM_SIZE;
+
+        (tx, Error::Success as _)
+    }
+
+    fn reject(&mut self, _: &mut [u8]) -> (usize, u16) {
+        Uploader::release(self.accessor);
+
+        (0, Error::CommandNotAllowed as _)
+    }
+}
@@ -13,12 +13,44 @@
 *  See the License for the specific language governing permissions and
 *  limitations under the License.
 ********************************************************************************/
+use bolos::PIC;
+
 use crate::constants::{version::*, ApduError};
 use crate::dispatcher::ApduHandler;
-use crate::utils::ApduBufferRead;
+use crate::handlers::configuration::GetAppConfiguration;
+use crate::utils::{git_dirty, ApduBufferRead, GIT_COMMIT_HASH};
 
 pub struct GetVersion {}
 
+impl GetVersion {
+    /// P1 to get the metadata of the build instead of the version
+    pub const P1_BUILD_INFO: u8 = 0x01;
+
+    /// Writes the commit, dirty flag, features and version file the app was built from
+    ///
+    /// Returns the number of bytes written
+    #[inline(never)]
+    fn build_info(out: &mut [u8]) -> Result<usize, ApduError> {
+        let hash = PIC::new(GIT_COMMIT_HASH.as_bytes()).into_inner();
+        let version_file = PIC::new(VERSION_FILE.as_bytes()).into_inner();
+
+        let len = 1 + hash.len() + 2 + 1 + version_file.len();
+        let out = out.get_mut(..len).ok_or(ApduError::OutputBufferTooSmall)?;
+
+        let (len_hash, rest) = out.split_at_mut(1);
+        len_hash[0] = hash.len() as u8;
+        let (out_hash, rest) = rest.split_at_mut(hash.len());
+        out_hash.copy_from_slice(hash);
+
+        rest[0] = git_dirty() as u8;
+        rest[1] = GetAppConfiguration::features();
+        rest[2] = version_file.len() as u8;
+        rest[3..].copy_from_slice(version_file);
+
+        Ok(len)
+    }
+}
+
 impl ApduHandler for GetVersion {
     #[inline(never)]
     fn handle<'apdu>(
@@ -29,6 +61,11 @@ impl ApduHandler for GetVersion {
         crate::sys::zemu_log_stack("GetVersion\x00");
         *tx = 0;
 
+        if apdu_buffer.p1() == Self::P1_BUILD_INFO {
+            *tx = Self::build_info(apdu_buffer.write())? as u32;
+            return Ok(());
+        }
+
         let apdu_buffer = apdu_buffer.write();
         apdu_buffer[0] = 0; //Debug mode
                             // Version
@@ -45,3 +82,29 @@ impl ApduHandler for GetVersion {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assert_error_code,
+        constants::instructions::{CLA, INS_GET_VERSION},
+        handle_apdu_raw,
+    };
+    use std::convert::TryInto;
+
+    #[test]
+    fn build_info() {
+        let (_, tx, out) =
+            handle_apdu_raw(&[CLA, INS_GET_VERSION, GetVersion::P1_BUILD_INFO, 0, 0]);
+        assert_error_code!(tx, out, ApduError::Success);
+
+        let hash_len = out[0] as usize;
+        assert_eq!(&out[1..1 + hash_len], GIT_COMMIT_HASH.as_bytes());
+
+        let rest = &out[1 + hash_len..tx as usize - 2];
+        assert_eq!(rest[0], git_dirty() as u8);
+        assert_eq!(rest[2] as usize, rest[3..].len());
+        assert_eq!(&rest[3..], VERSION_FILE.as_bytes());
+    }
+}
@@ -22,6 +22,8 @@ extern crate no_std_compat as std;
 pub mod constants;
 pub mod dispatcher;
 mod handlers;
+mod parser;
+mod path;
 mod sys;
 
 pub use handlers::ZPacketType as PacketType;
@@ -13,9 +13,78 @@
 *  See the License for the specific language governing permissions and
 *  limitations under the License.
 ********************************************************************************/
+use arrayref::array_ref;
+use bolos::{pic_str, PIC};
 use zemu_sys::ViewError;
 
-use crate::{crypto::Curve, handlers::parser_common::ParserError};
+use crate::{
+    constants::ApduError,
+    crypto::felt::{Felt, U256},
+    handlers::handle_ui_message,
+    utils::{hex_encode, u256_to_decimal, ApduPanic},
+};
+
+mod account;
+pub use account::{AccountLayout, AccountParams};
+
+mod address;
+pub use address::{checksum_address, render_address};
+
+mod declare;
+pub use declare::{DeclareV2, DeclareV3};
+
+mod felt_format;
+pub use felt_format::{render_felt, short_string};
+
+mod deploy_account;
+pub use deploy_account::{contract_address, DeployAccountV1, DeployAccountV3};
+
+mod invoke;
+pub use invoke::{InvokeV1, InvokeV3};
+
+mod multicall;
+pub use multicall::{Call, ExecuteCalldata};
+
+mod network;
+pub use network::{render_network, Network};
+
+mod selectors;
+pub use selectors::{render_selector, selector_name};
+
+mod starkex;
+pub use starkex::{StarkExLimitOrder, StarkExTransfer};
+
+mod tokens;
+pub use tokens::{TokenOperation, TokenOperationKind};
+
+mod typed_data;
+pub use typed_data::TypedData;
+
+mod v3;
+pub use v3::CommonFieldsV3;
+
+pub const FELT_SIZE: usize = 32;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "derive-debug"), derive(Debug))]
+pub enum ParserError {
+    /// The input ended before all the fields could be read
+    UnexpectedBufferEnd,
+    /// There was still data left after all the fields were read
+    UnexpectedData,
+    /// The value read is not a valid field element
+    InvalidFelt,
+    /// The value read doesn't fit the expected range
+    ValueOutOfRange,
+    /// The input can be decoded in more than one way
+    Ambiguous,
+}
+
+impl From<ParserError> for ApduError {
+    fn from(_: ParserError) -> Self {
+        Self::DataInvalid
+    }
+}
 
 ///This trait defines the interface useful in the UI context
 /// so that all the different OperationTypes or other items can handle their own UI
@@ -44,3 +113,246 @@ pub trait DisplayableItem {
         page: u8,
     ) -> Result<u8, ViewError>;
 }
+
+/// Reads big-endian encoded felts sequentially from a byte slice
+pub struct FeltReader<'b> {
+    data: &'b [u8],
+}
+
+impl<'b> FeltReader<'b> {
+    pub fn new(data: &'b [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Reads a single felt, verifying it's smaller than the field modulus
+    pub fn felt(&mut self) -> Result<&'b [u8; FELT_SIZE], ParserError> {
+        if self.data.len() < FELT_SIZE {
+            return Err(ParserError::UnexpectedBufferEnd);
+        }
+
+        let (felt, rest) = self.data.split_at(FELT_SIZE);
+        let felt = array_ref![felt, 0, FELT_SIZE];
+        Felt::from_be_bytes(felt).ok_or(ParserError::InvalidFelt)?;
+
+        self.data = rest;
+        Ok(felt)
+    }
+
+    /// Reads a felt and interprets it as a length
+    pub fn length(&mut self) -> Result<usize, ParserError> {
+        let felt = self.felt()?;
+        felt_to_u64(felt)
+            .filter(|&n| n <= u16::MAX as u64)
+            .map(|n| n as usize)
+            .ok_or(ParserError::ValueOutOfRange)
+    }
+
+    /// Reads a felt which must fit in an u64
+    pub fn u64(&mut self) -> Result<u64, ParserError> {
+        let felt = self.felt()?;
+        felt_to_u64(felt).ok_or(ParserError::ValueOutOfRange)
+    }
+
+    /// Reads a felt which must fit in an u128
+    pub fn u128(&mut self) -> Result<u128, ParserError> {
+        let felt = self.felt()?;
+        felt_to_u128(felt).ok_or(ParserError::ValueOutOfRange)
+    }
+
+    /// Reads `n` consecutive felts
+    pub fn felts(&mut self, n: usize) -> Result<&'b [u8], ParserError> {
+        let size = n * FELT_SIZE;
+        if self.data.len() < size {
+            return Err(ParserError::UnexpectedBufferEnd);
+        }
+
+        let (felts, rest) = self.data.split_at(size);
+        for felt in felts_iter(felts) {
+            Felt::from_be_bytes(felt).ok_or(ParserError::InvalidFelt)?;
+        }
+
+        self.data = rest;
+        Ok(felts)
+    }
+
+    /// Reads an array of felts, prefixed by its length
+    pub fn array(&mut self) -> Result<&'b [u8], ParserError> {
+        let len = self.length()?;
+        self.felts(len)
+    }
+
+    /// Returns the data not read yet
+    pub fn remaining(&self) -> &'b [u8] {
+        self.data
+    }
+
+    /// Verifies there's no data left to read
+    pub fn finish(self) -> Result<(), ParserError> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(ParserError::UnexpectedData)
+        }
+    }
+}
+
+/// Iterate over the felts of an array previously read with [`FeltReader`]
+pub fn felts_iter(data: &[u8]) -> impl Iterator<Item = &[u8; FELT_SIZE]> {
+    data.chunks_exact(FELT_SIZE)
+        .map(|felt| array_ref![felt, 0, FELT_SIZE])
+}
+
+/// Converts a felt previously read with [`FeltReader`]
+pub fn to_felt(felt: &[u8; FELT_SIZE]) -> Felt {
+    //already verified when parsing
+    Felt::from_be_bytes(felt).apdu_unwrap()
+}
+
+/// Returns the value of the felt if it fits in an u64
+pub fn felt_to_u64(felt: &[u8; FELT_SIZE]) -> Option<u64> {
+    if felt[..FELT_SIZE - 8].iter().any(|&b| b != 0) {
+        return None;
+    }
+
+    Some(u64::from_be_bytes(*array_ref![felt, FELT_SIZE - 8, 8]))
+}
+
+/// Returns the value of the felt if it fits in an u128
+pub fn felt_to_u128(felt: &[u8; FELT_SIZE]) -> Option<u128> {
+    if felt[..FELT_SIZE - 16].iter().any(|&b| b != 0) {
+        return None;
+    }
+
+    Some(u128::from_be_bytes(*array_ref![felt, FELT_SIZE - 16, 16]))
+}
+
+/// Copies the given title to the title buffer, truncating if necessary
+pub fn render_title(content: &[u8], title: &mut [u8]) {
+    let len = core::cmp::min(content.len(), title.len());
+    title[..len].copy_from_slice(&content[..len]);
+}
+
+/// Renders the felt as 0x-prefixed hex
+#[inline(never)]
+pub fn render_felt_hex(
+    felt: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    let mut hex_buf = [0; 2 + FELT_SIZE * 2];
+    hex_buf[0] = b'0';
+    hex_buf[1] = b'x';
+    //this is impossible that will error since the sizes are all checked
+    let len = hex_encode(&felt[..], &mut hex_buf[2..]).apdu_unwrap();
+
+    handle_ui_message(&hex_buf[..2 + len], message, page)
+}
+
+/// Renders the felt as a decimal number
+#[inline(never)]
+pub fn render_felt_decimal(
+    felt: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    render_amount(&U256::from_be_bytes(felt), 0, &[], message, page)
+}
+
+/// Renders the felt as a short string (ASCII encoded in the felt bytes)
+///
+/// Falls back to hex when the felt doesn't decode to printable characters
+#[inline(never)]
+pub fn render_short_string(
+    felt: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    match short_string(felt) {
+        Some(text) => handle_ui_message(text, message, page),
+        None => render_felt_hex(felt, message, page),
+    }
+}
+
+/// Renders `value` as a decimal amount with `decimals` fractional digits,
+/// followed by `ticker`
+#[inline(never)]
+pub fn render_amount(
+    value: &U256,
+    decimals: usize,
+    ticker: &[u8],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    //78 digits, the decimal point and a leading zero, plus the ticker
+    let mut text = TextBuffer::<{ 80 + 12 }>::new();
+    text.push_decimal(value, decimals);
+    if !ticker.is_empty() {
+        text.push(pic_str!(b" ")).push(ticker);
+    }
+
+    handle_ui_message(text.as_bytes(), message, page)
+}
+
+/// Fixed capacity buffer to compose text for the UI
+///
+/// Content exceeding the capacity is truncated
+pub struct TextBuffer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for TextBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> TextBuffer<N> {
+    pub fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) -> &mut Self {
+        let n = core::cmp::min(bytes.len(), N - self.len);
+        self.buf[self.len..self.len + n].copy_from_slice(&bytes[..n]);
+        self.len += n;
+
+        self
+    }
+
+    /// Appends `value` in base 10, with `decimals` fractional digits
+    ///
+    /// Nothing is appended if the number doesn't fit
+    pub fn push_decimal(&mut self, value: &U256, decimals: usize) -> &mut Self {
+        if let Ok(len) = u256_to_decimal(value, decimals, &mut self.buf[self.len..]) {
+            self.len += len;
+        }
+
+        self
+    }
+
+    /// Appends the felt as 0x-prefixed hex
+    pub fn push_felt_hex(&mut self, felt: &[u8; FELT_SIZE]) -> &mut Self {
+        let mut hex_buf = [0; FELT_SIZE * 2];
+        //this is impossible that will error since the sizes are all checked
+        let len = hex_encode(&felt[..], &mut hex_buf).apdu_unwrap();
+
+        self.push(pic_str!(b"0x")).push(&hex_buf[..len])
+    }
+
+    /// Appends the address as checksummed hex
+    pub fn push_address(&mut self, address: &[u8; FELT_SIZE]) -> &mut Self {
+        self.push(&checksum_address(address))
+    }
+
+    pub fn push_u64(&mut self, n: u64) -> &mut Self {
+        self.push_decimal(&U256::from_u64(n), 0)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
@@ -0,0 +1,234 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Counterfactual addresses of the supported account contracts
+
+use core::convert::TryFrom;
+
+use bolos::{pic_str, PIC};
+
+use crate::crypto::felt::Felt;
+
+use super::{contract_address, felts_iter, to_felt, ParserError, FELT_SIZE};
+
+/// Maximum number of felts in the constructor calldata template
+pub const MAX_TEMPLATE_LEN: usize = 5;
+
+/// Returns true if `felt` is the placeholder for the public key
+/// in the constructor calldata template (all bytes set to 0xFF)
+///
+/// The placeholder is never a valid felt, so it can't be confused with an argument
+fn is_public_key_placeholder(felt: &[u8; FELT_SIZE]) -> bool {
+    felt.iter().all(|&b| b == 0xFF)
+}
+
+/// Account contracts whose address can be derived from the public key
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum AccountLayout {
+    /// Constructor: `[public_key]`
+    OpenZeppelin = 1,
+    /// Constructor: `[owner = public_key, guardian = 0]`
+    Argent = 2,
+    /// Constructor: `[public_key]`, of the base account class
+    Braavos = 3,
+}
+
+impl TryFrom<u8> for AccountLayout {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::OpenZeppelin),
+            2 => Ok(Self::Argent),
+            3 => Ok(Self::Braavos),
+            _ => Err(()),
+        }
+    }
+}
+
+impl AccountLayout {
+    /// Label to show along the address
+    pub fn label(self) -> &'static [u8] {
+        match self {
+            Self::OpenZeppelin => pic_str!(b"OZ Account"),
+            Self::Argent => pic_str!(b"Argent Account"),
+            Self::Braavos => pic_str!(b"Braavos Account"),
+        }
+    }
+
+    /// Writes the default constructor calldata of the account to `out`,
+    /// returning the number of felts written
+    fn default_calldata(self, public_key: &Felt, out: &mut [Felt; MAX_TEMPLATE_LEN]) -> usize {
+        match self {
+            Self::OpenZeppelin | Self::Braavos => {
+                out[0] = *public_key;
+                1
+            }
+            Self::Argent => {
+                out[0] = *public_key;
+                out[1] = Felt::ZERO;
+                2
+            }
+        }
+    }
+}
+
+/// Parameters to compute the address of an account deployed with DEPLOY_ACCOUNT
+///
+/// Encoded as:
+/// class_hash, [contract_address_salt, [constructor_calldata...]]
+///
+/// Both the salt and the constructor calldata are optional, when omitted
+/// the public key is used as salt and the calldata of `layout` is used.
+/// In the constructor calldata, felts with all bytes set to 0xFF are replaced by the public key
+pub struct AccountParams<'b> {
+    pub layout: AccountLayout,
+    pub class_hash: &'b [u8; FELT_SIZE],
+    pub salt: Option<&'b [u8; FELT_SIZE]>,
+    pub template: &'b [u8],
+}
+
+impl<'b> AccountParams<'b> {
+    #[inline(never)]
+    pub fn parse(layout: AccountLayout, data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = super::FeltReader::new(data);
+
+        let class_hash = reader.felt()?;
+        let salt = if reader.remaining().is_empty() {
+            None
+        } else {
+            Some(reader.felt()?)
+        };
+
+        let template = reader.remaining();
+        if !template.chunks_exact(FELT_SIZE).remainder().is_empty() {
+            return Err(ParserError::UnexpectedBufferEnd);
+        }
+        if template.len() > MAX_TEMPLATE_LEN * FELT_SIZE {
+            return Err(ParserError::UnexpectedData);
+        }
+        for felt in felts_iter(template) {
+            if !is_public_key_placeholder(felt) {
+                Felt::from_be_bytes(felt).ok_or(ParserError::InvalidFelt)?;
+            }
+        }
+
+        Ok(Self {
+            layout,
+            class_hash,
+            salt,
+            template,
+        })
+    }
+
+    /// Computes the address of the account of `public_key`
+    #[inline(never)]
+    pub fn address(&self, public_key: &Felt) -> Felt {
+        let mut calldata = [Felt::ZERO; MAX_TEMPLATE_LEN];
+        let len = if self.template.is_empty() {
+            self.layout.default_calldata(public_key, &mut calldata)
+        } else {
+            for (out, felt) in calldata.iter_mut().zip(felts_iter(self.template)) {
+                *out = if is_public_key_placeholder(felt) {
+                    *public_key
+                } else {
+                    to_felt(felt)
+                };
+            }
+
+            self.template.len() / FELT_SIZE
+        };
+
+        let salt = self.salt.map(to_felt).unwrap_or(*public_key);
+
+        contract_address(
+            &Felt::ZERO,
+            &salt,
+            &to_felt(self.class_hash),
+            calldata[..len].iter().copied(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //public key of the private key 1, computed with starknet-rs
+    const PUBLIC_KEY: &str = "01ef15c18599971b7beced415a40f0c7deacfd9b0d1819e03d723d8bc943cfca";
+
+    const OZ_CLASS_HASH: &str = "061dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f";
+
+    const ARGENT_CLASS_HASH: &str =
+        "01a736d6ed154502257f02b1ccdf4d9d1089f80811cd6acad48e6b6a9d1f2003";
+
+    fn felt(s: &str) -> Felt {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(&hex::decode(s).unwrap());
+        Felt::from_be_bytes(&bytes).unwrap()
+    }
+
+    fn address(layout: AccountLayout, data: &str) -> std::string::String {
+        let data = hex::decode(data).unwrap();
+        let params = AccountParams::parse(layout, &data).unwrap();
+
+        hex::encode(params.address(&felt(PUBLIC_KEY)).to_be_bytes())
+    }
+
+    #[test]
+    fn default_layouts() {
+        assert_eq!(
+            address(AccountLayout::OpenZeppelin, OZ_CLASS_HASH),
+            "02b5cea709ab25612f9d014a3312f1c6dbd988add3bf396c1866db1b03b09194"
+        );
+        assert_eq!(
+            address(AccountLayout::Argent, ARGENT_CLASS_HASH),
+            "05a9939e8ec299610d6662878119e3fbffd98d9064e2d288ecdb1be4d61ca16d"
+        );
+
+        //the public key as explicit salt is the same as the default
+        let data = std::format!("{}{}", OZ_CLASS_HASH, PUBLIC_KEY);
+        assert_eq!(
+            address(AccountLayout::OpenZeppelin, &data),
+            "02b5cea709ab25612f9d014a3312f1c6dbd988add3bf396c1866db1b03b09194"
+        );
+    }
+
+    #[test]
+    fn template() {
+        //salt 7, calldata [0, public_key, 1]
+        let data = std::format!(
+            "{}{:064x}{:064x}{}{:064x}",
+            ARGENT_CLASS_HASH,
+            7,
+            0,
+            "ff".repeat(32),
+            1
+        );
+        assert_eq!(
+            address(AccountLayout::Argent, &data),
+            "018a85cfab5c77a13f3280e29fe8363919515a7785d474bd8ecbf724e6887982"
+        );
+
+        //template too long
+        let data = std::format!("{}{:064x}{}", OZ_CLASS_HASH, 7, "00".repeat(32 * 6));
+        let data = hex::decode(data).unwrap();
+        let err = AccountParams::parse(AccountLayout::OpenZeppelin, &data)
+            .err()
+            .unwrap();
+        assert_eq!(err, ParserError::UnexpectedData);
+    }
+}
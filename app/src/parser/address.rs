@@ -0,0 +1,105 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Checksummed rendering of Starknet addresses
+
+use zemu_sys::ViewError;
+
+use crate::{
+    crypto::keccak::keccak256,
+    handlers::handle_ui_message,
+    utils::{hex_encode, ApduPanic},
+};
+
+use super::FELT_SIZE;
+
+/// Length of a checksummed address: 0x followed by 64 hex digits
+pub const CHECKSUM_ADDRESS_LEN: usize = 2 + FELT_SIZE * 2;
+
+/// Encodes `address` as 0x-prefixed hex, with the case of each letter
+/// set by the keccak of the address (like EIP-55)
+///
+/// This is the same encoding used by starknet.js' `getChecksumAddress`:
+/// the hash is computed over the big-endian bytes of the address without
+/// leading zeroes, and the hex is padded to 64 digits
+#[inline(never)]
+pub fn checksum_address(address: &[u8; FELT_SIZE]) -> [u8; CHECKSUM_ADDRESS_LEN] {
+    let start = address
+        .iter()
+        .position(|&b| b != 0)
+        .unwrap_or(FELT_SIZE - 1);
+    let hash = keccak256(&address[start..]);
+
+    let mut out = [0; CHECKSUM_ADDRESS_LEN];
+    out[0] = b'0';
+    out[1] = b'x';
+    //this is impossible that will error since the sizes are all checked
+    hex_encode(&address[..], &mut out[2..]).apdu_unwrap();
+
+    for (i, c) in out[2..].iter_mut().enumerate() {
+        let byte = hash[i / 2];
+        let nibble = if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+
+        if nibble >= 8 {
+            c.make_ascii_uppercase();
+        }
+    }
+
+    out
+}
+
+/// Renders the address as checksummed hex
+#[inline(never)]
+pub fn render_address(
+    address: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    handle_ui_message(&checksum_address(address), message, page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checksum(address: &str) -> std::string::String {
+        let mut bytes = [0; 32];
+        let decoded = hex::decode(std::format!("{:0>64}", address)).unwrap();
+        bytes.copy_from_slice(&decoded);
+
+        std::string::String::from_utf8(checksum_address(&bytes).to_vec()).unwrap()
+    }
+
+    #[test]
+    fn starknet_js_vectors() {
+        assert_eq!(
+            checksum("2fd23d9182193775423497fc0c472e156c57c69e4089a1967fb288a2d84e914"),
+            "0x02Fd23d9182193775423497fc0c472E156C57C69E4089A1967fb288A2d84e914"
+        );
+        assert_eq!(
+            checksum("49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7"),
+            "0x049D36570D4e46f48e99674bd3fcc84644DdD6b96F7C741B1562B82f9e004dC7"
+        );
+    }
+
+    #[test]
+    fn short_address() {
+        //the hash skips the leading zeroes of the address
+        assert_eq!(
+            checksum("abcdefabcdef0123456789abcdefabcdefabcdef"),
+            "0x000000000000000000000000aBCdEFabcdef0123456789AbcDefaBcdefabcdef"
+        );
+    }
+}
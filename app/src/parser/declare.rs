@@ -0,0 +1,303 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! DECLARE transactions, declaring a Sierra class
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::crypto::{
+    felt::{Felt, U256},
+    pedersen::{compute_hash_on_elements, PedersenHasher},
+    poseidon::{poseidon_hash_many, PoseidonHasher},
+};
+
+use super::{
+    felts_iter, render_address, render_amount, render_felt, render_felt_decimal, render_felt_hex,
+    render_network, render_title, to_felt, CommonFieldsV3, DisplayableItem, FeltReader,
+    ParserError, FELT_SIZE,
+};
+
+/// "declare" as a short string
+const DECLARE_PREFIX: u64 = 0x6465636c617265;
+
+/// Decimals of the fee token (ETH) of v2 transactions
+const FEE_DECIMALS: usize = 18;
+
+/// Renders the items shared by all DECLARE versions
+fn render_declare_item(
+    item_n: u8,
+    sender_address: &[u8; FELT_SIZE],
+    class_hash: &[u8; FELT_SIZE],
+    compiled_class_hash: &[u8; FELT_SIZE],
+    title: &mut [u8],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    let (label, felt) = match item_n {
+        0 => {
+            render_title(pic_str!(b"Sender"), title);
+            return render_address(sender_address, message, page);
+        }
+        1 => (pic_str!(b"Class Hash"), class_hash),
+        2 => (pic_str!(b"Compiled Class Hash"), compiled_class_hash),
+        _ => return Err(ViewError::NoData),
+    };
+
+    render_title(label, title);
+    render_felt_hex(felt, message, page)
+}
+
+/// DECLARE v2 transaction
+///
+/// Encoded as a sequence of felts:
+/// sender_address, class_hash, compiled_class_hash, max_fee, nonce, chain_id
+pub struct DeclareV2<'b> {
+    pub sender_address: &'b [u8; FELT_SIZE],
+    pub class_hash: &'b [u8; FELT_SIZE],
+    pub compiled_class_hash: &'b [u8; FELT_SIZE],
+    pub max_fee: &'b [u8; FELT_SIZE],
+    pub nonce: &'b [u8; FELT_SIZE],
+    pub chain_id: &'b [u8; FELT_SIZE],
+}
+
+impl<'b> DeclareV2<'b> {
+    pub const VERSION: u64 = 2;
+
+    #[inline(never)]
+    pub fn parse(data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let tx = Self {
+            sender_address: reader.felt()?,
+            class_hash: reader.felt()?,
+            compiled_class_hash: reader.felt()?,
+            max_fee: reader.felt()?,
+            nonce: reader.felt()?,
+            chain_id: reader.felt()?,
+        };
+
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    /// Computes the transaction hash
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let class_hash = compute_hash_on_elements([to_felt(self.class_hash)]);
+
+        let mut hasher = PedersenHasher::default();
+        hasher.update(&Felt::from_u64(DECLARE_PREFIX));
+        hasher.update(&Felt::from_u64(Self::VERSION));
+        hasher.update(&to_felt(self.sender_address));
+        //entry_point_selector
+        hasher.update(&Felt::ZERO);
+        hasher.update(&class_hash);
+        hasher.update(&to_felt(self.max_fee));
+        hasher.update(&to_felt(self.chain_id));
+        hasher.update(&to_felt(self.nonce));
+        hasher.update(&to_felt(self.compiled_class_hash));
+
+        hasher.finalize()
+    }
+}
+
+impl<'b> DisplayableItem for DeclareV2<'b> {
+    fn num_items(&self) -> usize {
+        6
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        match item_n {
+            0..=2 => render_declare_item(
+                item_n,
+                self.sender_address,
+                self.class_hash,
+                self.compiled_class_hash,
+                title,
+                message,
+                page,
+            ),
+            3 => {
+                render_title(pic_str!(b"Max Fee"), title);
+                render_amount(
+                    &U256::from_be_bytes(self.max_fee),
+                    FEE_DECIMALS,
+                    pic_str!(b"ETH"),
+                    message,
+                    page,
+                )
+            }
+            4 => {
+                render_title(pic_str!(b"Nonce"), title);
+                render_felt_decimal(self.nonce, message, page)
+            }
+            5 => render_network(self.chain_id, title, message, page),
+            _ => Err(ViewError::NoData),
+        }
+    }
+}
+
+/// DECLARE v3 transaction
+///
+/// Encoded as a sequence of felts:
+/// sender_address, class_hash, compiled_class_hash, [`CommonFieldsV3`],
+/// account_deployment_data_len, account_deployment_data...
+pub struct DeclareV3<'b> {
+    pub sender_address: &'b [u8; FELT_SIZE],
+    pub class_hash: &'b [u8; FELT_SIZE],
+    pub compiled_class_hash: &'b [u8; FELT_SIZE],
+    pub common: CommonFieldsV3<'b>,
+    pub account_deployment_data: &'b [u8],
+}
+
+impl<'b> DeclareV3<'b> {
+    pub const VERSION: u64 = CommonFieldsV3::VERSION;
+
+    /// Parses the transaction, with L1 data gas bounds if `with_l1_data_gas`
+    #[inline(never)]
+    pub fn parse(data: &'b [u8], with_l1_data_gas: bool) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let tx = Self {
+            sender_address: reader.felt()?,
+            class_hash: reader.felt()?,
+            compiled_class_hash: reader.felt()?,
+            common: CommonFieldsV3::parse(&mut reader, with_l1_data_gas)?,
+            account_deployment_data: reader.array()?,
+        };
+
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    pub fn account_deployment_data_len(&self) -> usize {
+        self.account_deployment_data.len() / FELT_SIZE
+    }
+
+    /// Computes the transaction hash
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let mut hasher = PoseidonHasher::default();
+        hasher.update(&Felt::from_u64(DECLARE_PREFIX));
+        hasher.update(&Felt::from_u64(Self::VERSION));
+        hasher.update(&to_felt(self.sender_address));
+        self.common.hash_into(&mut hasher);
+        hasher.update(&poseidon_hash_many(
+            felts_iter(self.account_deployment_data).map(to_felt),
+        ));
+        hasher.update(&to_felt(self.class_hash));
+        hasher.update(&to_felt(self.compiled_class_hash));
+
+        hasher.finalize()
+    }
+}
+
+impl<'b> DisplayableItem for DeclareV3<'b> {
+    fn num_items(&self) -> usize {
+        3 + self.common.num_items() + self.account_deployment_data_len()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        if item_n < 3 {
+            return render_declare_item(
+                item_n,
+                self.sender_address,
+                self.class_hash,
+                self.compiled_class_hash,
+                title,
+                message,
+                page,
+            );
+        }
+
+        let mut idx = item_n as usize - 3;
+        if idx < self.common.num_items() {
+            return self.common.render_item(idx as u8, title, message, page);
+        }
+        idx -= self.common.num_items();
+
+        let felt = felts_iter(self.account_deployment_data)
+            .nth(idx)
+            .ok_or(ViewError::NoData)?;
+
+        render_title(pic_str!(b"Deployment Data"), title);
+        render_felt(felt, message, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIALIZED: &str = "04a2d5a3e5d5ab7ca6d4b0c37d2f1d0e9c6ca0dd1e2c8a8c4d3d4b5c6e7f809105ffbcfeb50d200a0677c48a129a11245a3fc519d1d98d76882d1c9a1b19c6ed06c5cb6b1c2a6b2f2d76b6b4c7d1b22cdf5a7ab3f0b6a7a1f4df0e0f0fb9d2a1000000000000000000000000000000000000000000000000002386f26fc10000000000000000000000000000000000000000000000000000000000000000000500000000000000000000000000000000000000000000000000534e5f4d41494e";
+
+    const EXPECTED_HASH: &str = "06ab04e598afdcbcc40966cac7385ff4e83509386e332e98ec53da7a407e44c8";
+
+    const SERIALIZED_V3: &str = "04a2d5a3e5d5ab7ca6d4b0c37d2f1d0e9c6ca0dd1e2c8a8c4d3d4b5c6e7f809105ffbcfeb50d200a0677c48a129a11245a3fc519d1d98d76882d1c9a1b19c6ed06c5cb6b1c2a6b2f2d76b6b4c7d1b22cdf5a7ab3f0b6a7a1f4df0e0f0fb9d2a1000000000000000000000000000000000000000000000000000000000000000500000000000000000000000000000000000000000000000000534e5f4d41494e000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000186a000000000000000000000000000000000000000000000000000005af3107a4000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000";
+
+    const EXPECTED_HASH_V3: &str =
+        "0367f9bd77c6e98e17c7cd276f8e6ab79b64a668b1e2b1d9d17c7264f7441f06";
+
+    #[test]
+    fn parse_and_hash() {
+        let data = hex::decode(SERIALIZED).unwrap();
+        let tx = DeclareV2::parse(&data).unwrap();
+
+        assert_eq!(tx.num_items(), 6);
+        assert_eq!(hex::encode(tx.hash().to_be_bytes()), EXPECTED_HASH);
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        tx.render_item(2, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"Compiled Class Hash\0"));
+        assert!(message.starts_with(b"0x06c5cb6b"));
+
+        //missing the chain id
+        let err = DeclareV2::parse(&data[..data.len() - FELT_SIZE])
+            .err()
+            .unwrap();
+        assert_eq!(err, ParserError::UnexpectedBufferEnd);
+    }
+
+    #[test]
+    fn parse_and_hash_v3() {
+        let data = hex::decode(SERIALIZED_V3).unwrap();
+        let tx = DeclareV3::parse(&data, false).unwrap();
+
+        assert_eq!(tx.account_deployment_data_len(), 0);
+        assert_eq!(tx.num_items(), 3 + 5);
+        assert_eq!(hex::encode(tx.hash().to_be_bytes()), EXPECTED_HASH_V3);
+
+        //extra data at the end
+        let mut extra = data;
+        extra.extend_from_slice(&[0; FELT_SIZE]);
+        let err = DeclareV3::parse(&extra, false).err().unwrap();
+        assert_eq!(err, ParserError::UnexpectedData);
+    }
+}
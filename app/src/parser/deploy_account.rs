@@ -0,0 +1,387 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! DEPLOY_ACCOUNT transactions, deploying the account contract of the signer
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::crypto::{
+    felt::{Felt, U256},
+    pedersen::{compute_hash_on_elements, PedersenHasher},
+    poseidon::{poseidon_hash_many, PoseidonHasher},
+};
+
+use super::{
+    felts_iter, render_address, render_amount, render_felt, render_felt_decimal, render_felt_hex,
+    render_network, render_title, to_felt, CommonFieldsV3, DisplayableItem, FeltReader,
+    ParserError, FELT_SIZE,
+};
+
+/// "deploy_account" as a short string
+const DEPLOY_ACCOUNT_PREFIX: u128 = 0x6465706c6f795f6163636f756e74;
+
+/// "STARKNET_CONTRACT_ADDRESS" as a short string, split in the low and high 128 bits
+const CONTRACT_ADDRESS_PREFIX: (u128, u128) =
+    (0x434f4e54524143545f41444452455353, 0x535441524b4e45545f);
+
+/// Decimals of the fee token (ETH) of v1 transactions
+const FEE_DECIMALS: usize = 18;
+
+/// Computes the address of a contract deployed by `deployer`
+///
+/// The result is normalized to be smaller than 2^251 - 256, as done by the sequencer
+#[inline(never)]
+pub fn contract_address(
+    deployer: &Felt,
+    salt: &Felt,
+    class_hash: &Felt,
+    constructor_calldata: impl IntoIterator<Item = Felt>,
+) -> Felt {
+    let (low, high) = CONTRACT_ADDRESS_PREFIX;
+
+    let mut hasher = PedersenHasher::default();
+    //the prefix is a short string smaller than 2^200, so this is always a valid felt
+    hasher.update(&Felt::from_u256_reduced(U256::from_low_high(low, high)));
+    hasher.update(deployer);
+    hasher.update(salt);
+    hasher.update(class_hash);
+    hasher.update(&compute_hash_on_elements(constructor_calldata));
+    let address = hasher.finalize().to_u256();
+
+    //2^251 - 256
+    let bound = U256([
+        0xffffffffffffff00,
+        0xffffffffffffffff,
+        0xffffffffffffffff,
+        0x07ffffffffffffff,
+    ]);
+
+    //the felt modulus is smaller than twice the bound,
+    // so a single subtraction is enough
+    if address >= bound {
+        Felt::from_u256_reduced(address.overflowing_sub(&bound).0)
+    } else {
+        Felt::from_u256_reduced(address)
+    }
+}
+
+/// Renders the given account address, computed on device
+fn render_account(
+    address: &Felt,
+    title: &mut [u8],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    render_title(pic_str!(b"Account"), title);
+    render_address(&address.to_be_bytes(), message, page)
+}
+
+/// DEPLOY_ACCOUNT v1 transaction
+///
+/// Encoded as a sequence of felts:
+/// class_hash, contract_address_salt, max_fee, nonce, chain_id,
+/// constructor_calldata_len, constructor_calldata...
+pub struct DeployAccountV1<'b> {
+    pub class_hash: &'b [u8; FELT_SIZE],
+    pub salt: &'b [u8; FELT_SIZE],
+    pub max_fee: &'b [u8; FELT_SIZE],
+    pub nonce: &'b [u8; FELT_SIZE],
+    pub chain_id: &'b [u8; FELT_SIZE],
+    pub constructor_calldata: &'b [u8],
+    /// Address of the account being deployed
+    pub address: Felt,
+}
+
+impl<'b> DeployAccountV1<'b> {
+    pub const VERSION: u64 = 1;
+
+    #[inline(never)]
+    pub fn parse(data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let class_hash = reader.felt()?;
+        let salt = reader.felt()?;
+        let max_fee = reader.felt()?;
+        let nonce = reader.felt()?;
+        let chain_id = reader.felt()?;
+        let constructor_calldata = reader.array()?;
+        reader.finish()?;
+
+        let address = contract_address(
+            &Felt::ZERO,
+            &to_felt(salt),
+            &to_felt(class_hash),
+            felts_iter(constructor_calldata).map(to_felt),
+        );
+
+        Ok(Self {
+            class_hash,
+            salt,
+            max_fee,
+            nonce,
+            chain_id,
+            constructor_calldata,
+            address,
+        })
+    }
+
+    pub fn constructor_calldata_len(&self) -> usize {
+        self.constructor_calldata.len() / FELT_SIZE
+    }
+
+    /// Computes the transaction hash
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let calldata_hash = compute_hash_on_elements(
+            [self.class_hash, self.salt]
+                .iter()
+                .copied()
+                .chain(felts_iter(self.constructor_calldata))
+                .map(to_felt),
+        );
+
+        let mut hasher = PedersenHasher::default();
+        hasher.update(&Felt::from_u128(DEPLOY_ACCOUNT_PREFIX));
+        hasher.update(&Felt::from_u64(Self::VERSION));
+        hasher.update(&self.address);
+        //entry_point_selector
+        hasher.update(&Felt::ZERO);
+        hasher.update(&calldata_hash);
+        hasher.update(&to_felt(self.max_fee));
+        hasher.update(&to_felt(self.chain_id));
+        hasher.update(&to_felt(self.nonce));
+
+        hasher.finalize()
+    }
+}
+
+impl<'b> DisplayableItem for DeployAccountV1<'b> {
+    fn num_items(&self) -> usize {
+        6 + self.constructor_calldata_len()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        match item_n {
+            0 => render_account(&self.address, title, message, page),
+            1 => {
+                render_title(pic_str!(b"Class Hash"), title);
+                render_felt_hex(self.class_hash, message, page)
+            }
+            2 => {
+                render_title(pic_str!(b"Salt"), title);
+                render_felt_hex(self.salt, message, page)
+            }
+            3 => {
+                render_title(pic_str!(b"Max Fee"), title);
+                render_amount(
+                    &U256::from_be_bytes(self.max_fee),
+                    FEE_DECIMALS,
+                    pic_str!(b"ETH"),
+                    message,
+                    page,
+                )
+            }
+            4 => {
+                render_title(pic_str!(b"Nonce"), title);
+                render_felt_decimal(self.nonce, message, page)
+            }
+            5 => render_network(self.chain_id, title, message, page),
+            n => {
+                let felt = felts_iter(self.constructor_calldata)
+                    .nth((n - 6) as usize)
+                    .ok_or(ViewError::NoData)?;
+
+                render_title(pic_str!(b"Constructor Arg"), title);
+                render_felt(felt, message, page)
+            }
+        }
+    }
+}
+
+/// DEPLOY_ACCOUNT v3 transaction
+///
+/// Encoded as a sequence of felts:
+/// class_hash, contract_address_salt, [`CommonFieldsV3`],
+/// constructor_calldata_len, constructor_calldata...
+pub struct DeployAccountV3<'b> {
+    pub class_hash: &'b [u8; FELT_SIZE],
+    pub salt: &'b [u8; FELT_SIZE],
+    pub common: CommonFieldsV3<'b>,
+    pub constructor_calldata: &'b [u8],
+    /// Address of the account being deployed
+    pub address: Felt,
+}
+
+impl<'b> DeployAccountV3<'b> {
+    pub const VERSION: u64 = CommonFieldsV3::VERSION;
+
+    /// Parses the transaction, with L1 data gas bounds if `with_l1_data_gas`
+    #[inline(never)]
+    pub fn parse(data: &'b [u8], with_l1_data_gas: bool) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let class_hash = reader.felt()?;
+        let salt = reader.felt()?;
+        let common = CommonFieldsV3::parse(&mut reader, with_l1_data_gas)?;
+        let constructor_calldata = reader.array()?;
+        reader.finish()?;
+
+        let address = contract_address(
+            &Felt::ZERO,
+            &to_felt(salt),
+            &to_felt(class_hash),
+            felts_iter(constructor_calldata).map(to_felt),
+        );
+
+        Ok(Self {
+            class_hash,
+            salt,
+            common,
+            constructor_calldata,
+            address,
+        })
+    }
+
+    pub fn constructor_calldata_len(&self) -> usize {
+        self.constructor_calldata.len() / FELT_SIZE
+    }
+
+    /// Computes the transaction hash
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let mut hasher = PoseidonHasher::default();
+        hasher.update(&Felt::from_u128(DEPLOY_ACCOUNT_PREFIX));
+        hasher.update(&Felt::from_u64(Self::VERSION));
+        hasher.update(&self.address);
+        self.common.hash_into(&mut hasher);
+        hasher.update(&poseidon_hash_many(
+            felts_iter(self.constructor_calldata).map(to_felt),
+        ));
+        hasher.update(&to_felt(self.class_hash));
+        hasher.update(&to_felt(self.salt));
+
+        hasher.finalize()
+    }
+}
+
+impl<'b> DisplayableItem for DeployAccountV3<'b> {
+    fn num_items(&self) -> usize {
+        3 + self.common.num_items() + self.constructor_calldata_len()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        match item_n {
+            0 => return render_account(&self.address, title, message, page),
+            1 => {
+                render_title(pic_str!(b"Class Hash"), title);
+                return render_felt_hex(self.class_hash, message, page);
+            }
+            2 => {
+                render_title(pic_str!(b"Salt"), title);
+                return render_felt_hex(self.salt, message, page);
+            }
+            _ => {}
+        }
+
+        let mut idx = item_n as usize - 3;
+        if idx < self.common.num_items() {
+            return self.common.render_item(idx as u8, title, message, page);
+        }
+        idx -= self.common.num_items();
+
+        let felt = felts_iter(self.constructor_calldata)
+            .nth(idx)
+            .ok_or(ViewError::NoData)?;
+
+        render_title(pic_str!(b"Constructor Arg"), title);
+        render_felt(felt, message, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //OpenZeppelin account, with public key 0x1234
+    const SERIALIZED: &str = "061dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f000000000000000000000000000000000000000000000000000000000000123400000000000000000000000000000000000000000000000000038d7ea4c68000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000534e5f5345504f4c494100000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000001234";
+
+    const EXPECTED_HASH: &str = "0215bf204e6519a708f52b15da8555f536bab3d6a6626cd6f585ae2f87f00fa7";
+
+    const SERIALIZED_V3: &str = "061dac032f228abef9c6626f995015233097ae253a7f72d68552db02f2971b8f0000000000000000000000000000000000000000000000000000000000001234000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000534e5f5345504f4c4941000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000186a000000000000000000000000000000000000000000000000000005af3107a40000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000001234";
+
+    const EXPECTED_HASH_V3: &str =
+        "050b52fb3d3cedcb37577d912a748dc2bd4965b51b707609b49632df442cd816";
+
+    //computed with starknet-rs
+    const EXPECTED_ADDRESS: &str =
+        "0728b3f5770b5d45306f9ef58db59722652f22008ce0196a94f2986785a7b252";
+
+    #[test]
+    fn parse_and_hash() {
+        let data = hex::decode(SERIALIZED).unwrap();
+        let tx = DeployAccountV1::parse(&data).unwrap();
+
+        assert_eq!(hex::encode(tx.address.to_be_bytes()), EXPECTED_ADDRESS);
+        assert_eq!(tx.num_items(), 6 + 1);
+        assert_eq!(hex::encode(tx.hash().to_be_bytes()), EXPECTED_HASH);
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        tx.render_item(0, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"Account\0"));
+        assert!(message.starts_with(b"0x0728b3f5"));
+    }
+
+    #[test]
+    fn parse_and_hash_v3() {
+        let data = hex::decode(SERIALIZED_V3).unwrap();
+        let tx = DeployAccountV3::parse(&data, false).unwrap();
+
+        assert_eq!(hex::encode(tx.address.to_be_bytes()), EXPECTED_ADDRESS);
+        assert_eq!(tx.num_items(), 3 + 5 + 1);
+        assert_eq!(hex::encode(tx.hash().to_be_bytes()), EXPECTED_HASH_V3);
+    }
+
+    #[test]
+    fn malformed() {
+        let data = hex::decode(SERIALIZED).unwrap();
+
+        //missing the constructor calldata
+        let err = DeployAccountV1::parse(&data[..data.len() - FELT_SIZE])
+            .err()
+            .unwrap();
+        assert_eq!(err, ParserError::UnexpectedBufferEnd);
+
+        //extra data at the end
+        let mut extra = data;
+        extra.extend_from_slice(&[0; FELT_SIZE]);
+        let err = DeployAccountV1::parse(&extra).err().unwrap();
+        assert_eq!(err, ParserError::UnexpectedData);
+    }
+}
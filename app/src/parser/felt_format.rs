@@ -0,0 +1,262 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Representations of felts whose meaning is unknown
+//!
+//! A felt is shown in the clearest of its representations first,
+//! followed by the other ones on the next pages:
+//! * `"text"`, when the felt is a Cairo short string
+//! * `1234`, in base 10
+//! * `0x4d2`, in hex without leading zeros
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::{
+    crypto::felt::U256,
+    handlers::handle_ui_message,
+    utils::{hex_encode, ApduPanic},
+};
+
+use super::{TextBuffer, FELT_SIZE};
+
+/// Minimum number of characters for a felt to be shown as text first
+///
+/// Shorter strings are most likely small integers (0x41 is "A")
+const MIN_SHORT_STRING_LEN: usize = 3;
+
+/// Felts up to this number of bits are shown in base 10 first
+const MAX_DECIMAL_BITS: usize = 64;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "derive-debug"), derive(Debug))]
+pub enum FeltFormat {
+    ShortString,
+    Decimal,
+    Hex,
+}
+
+/// Returns the characters of the felt if it's a printable short string
+pub fn short_string(felt: &[u8; FELT_SIZE]) -> Option<&[u8]> {
+    //short strings are at most 31 characters
+    let start = felt.iter().position(|&c| c != 0)?;
+    let text = &felt[start..];
+
+    if start == 0 || !text.iter().all(|&c| (0x20..0x7f).contains(&c)) {
+        return None;
+    }
+
+    Some(text)
+}
+
+impl FeltFormat {
+    /// Returns the representations of the felt, the clearest first
+    pub fn choices(felt: &[u8; FELT_SIZE]) -> ([Self; 3], usize) {
+        let text = short_string(felt);
+        let is_text = text
+            .map(|text| {
+                text.len() >= MIN_SHORT_STRING_LEN && text.iter().any(u8::is_ascii_alphabetic)
+            })
+            .unwrap_or(false);
+
+        let choices = if is_text {
+            [Self::ShortString, Self::Decimal, Self::Hex]
+        } else if U256::from_be_bytes(felt).bits() <= MAX_DECIMAL_BITS {
+            [Self::Decimal, Self::Hex, Self::ShortString]
+        } else {
+            [Self::Hex, Self::Decimal, Self::ShortString]
+        };
+
+        //the short string is last unless it was picked
+        let n = if text.is_some() { 3 } else { 2 };
+        (choices, n)
+    }
+
+    /// Writes the felt in this representation
+    ///
+    /// Nothing is written for [`FeltFormat::ShortString`] if the felt is not one
+    pub fn write<const N: usize>(self, felt: &[u8; FELT_SIZE], text: &mut TextBuffer<N>) {
+        match self {
+            Self::ShortString => {
+                if let Some(string) = short_string(felt) {
+                    text.push(pic_str!(b"\""))
+                        .push(string)
+                        .push(pic_str!(b"\""));
+                }
+            }
+            Self::Decimal => {
+                text.push_decimal(&U256::from_be_bytes(felt), 0);
+            }
+            Self::Hex => {
+                let mut hex_buf = [0; FELT_SIZE * 2];
+                //this is impossible that will error since the sizes are all checked
+                let len = hex_encode(&felt[..], &mut hex_buf).apdu_unwrap();
+
+                //keep at least one digit
+                let start = hex_buf[..len - 1]
+                    .iter()
+                    .position(|&c| c != b'0')
+                    .unwrap_or(len - 1);
+                text.push(pic_str!(b"0x")).push(&hex_buf[start..len]);
+            }
+        }
+    }
+}
+
+/// Renders the felt in its clearest representation,
+/// the alternatives follow on the next pages
+#[inline(never)]
+pub fn render_felt(felt: &[u8; FELT_SIZE], message: &mut [u8], page: u8) -> Result<u8, ViewError> {
+    //76 digits for 2^252, more than the hex or a short string need
+    type Text = TextBuffer<80>;
+
+    let capacity = message.len().checked_sub(1).ok_or(ViewError::Unknown)?;
+    if capacity == 0 {
+        return Err(ViewError::Unknown);
+    }
+
+    let pages_of = |text: &Text| -> u8 {
+        let len = core::cmp::max(text.as_bytes().len(), 1);
+        (len / capacity + (len % capacity != 0) as usize) as u8
+    };
+
+    let (choices, n) = FeltFormat::choices(felt);
+
+    let mut total = 0;
+    let mut shown = false;
+    for &format in choices[..n].iter() {
+        let mut text = Text::new();
+        format.write(felt, &mut text);
+
+        let pages = pages_of(&text);
+        if !shown && page < total + pages {
+            handle_ui_message(text.as_bytes(), message, page - total)?;
+            shown = true;
+        }
+        total += pages;
+    }
+
+    if shown {
+        Ok(total)
+    } else {
+        Err(ViewError::NoData)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::felt::{Modulus, StarkField};
+    use std::{string::String, vec::Vec};
+
+    fn felt_from_u128(n: u128) -> [u8; FELT_SIZE] {
+        let mut felt = [0; FELT_SIZE];
+        felt[FELT_SIZE - 16..].copy_from_slice(&n.to_be_bytes());
+        felt
+    }
+
+    fn felt_from_str(s: &str) -> [u8; FELT_SIZE] {
+        let mut felt = [0; FELT_SIZE];
+        felt[FELT_SIZE - s.len()..].copy_from_slice(s.as_bytes());
+        felt
+    }
+
+    /// Renders every page with a message of `len`
+    fn pages(felt: &[u8; FELT_SIZE], len: usize) -> Vec<String> {
+        let mut message = std::vec![0; len];
+        let n = render_felt(felt, &mut message, 0).unwrap();
+
+        (0..n)
+            .map(|page| {
+                assert!(matches!(render_felt(felt, &mut message, page), Ok(p) if p == n));
+                let len = message.iter().position(|&c| c == 0).unwrap();
+                String::from_utf8(message[..len].to_vec()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small_integers() {
+        assert_eq!(pages(&felt_from_u128(1234), 100), ["1234", "0x4d2"]);
+        assert_eq!(pages(&[0; FELT_SIZE], 100), ["0", "0x0"]);
+
+        //looks like text, but it's too short
+        assert_eq!(pages(&felt_from_u128(0x41), 100), ["65", "0x41", "\"A\""]);
+    }
+
+    #[test]
+    fn short_strings() {
+        assert_eq!(
+            pages(&felt_from_str("SN_MAIN"), 100),
+            ["\"SN_MAIN\"", "23448594291968334", "0x534e5f4d41494e"]
+        );
+
+        //printable, but no letters
+        assert_eq!(
+            pages(&felt_from_str("123"), 100),
+            ["3224115", "0x313233", "\"123\""]
+        );
+    }
+
+    #[test]
+    fn large_felts() {
+        let mut felt = [0xab; FELT_SIZE];
+        felt[0] = 0x04;
+        felt[1] = 0x00;
+
+        let rendered = pages(&felt, 100);
+        assert_eq!(rendered.len(), 2);
+        assert_eq!(
+            rendered[0],
+            "0x400abababababababababababababababababababababababababababababab"
+        );
+        assert!(rendered[1].bytes().all(|c| c.is_ascii_digit()));
+
+        //the largest felt has 76 digits
+        let mut max = [0; FELT_SIZE];
+        max.copy_from_slice(&StarkField::MODULUS.to_be_bytes());
+        max[FELT_SIZE - 1] -= 1;
+        assert_eq!(
+            pages(&max, 100)[1],
+            "3618502788666131213697322783095070105623107215331596699973092056135872020480"
+        );
+    }
+
+    #[test]
+    fn pages_each_representation() {
+        //12 characters per page
+        let rendered = pages(&felt_from_str("hello world"), 13);
+        assert_eq!(
+            rendered,
+            [
+                "\"hello world",
+                "\"",
+                "126207244316",
+                "550804821666",
+                "916",
+                "0x68656c6c6f",
+                "20776f726c64"
+            ]
+        );
+    }
+
+    #[test]
+    fn out_of_range_page() {
+        let mut message = [0; 100];
+        assert!(matches!(
+            render_felt(&felt_from_u128(7), &mut message, 2),
+            Err(ViewError::NoData)
+        ));
+    }
+}
@@ -0,0 +1,342 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::crypto::{
+    felt::{Felt, U256},
+    pedersen::{compute_hash_on_elements, PedersenHasher},
+    poseidon::{poseidon_hash_many, PoseidonHasher},
+};
+
+use super::{
+    felts_iter, render_address, render_amount, render_felt, render_felt_decimal, render_network,
+    render_title, to_felt, CommonFieldsV3, DisplayableItem, ExecuteCalldata, FeltReader,
+    ParserError, FELT_SIZE,
+};
+
+/// "invoke" as a short string
+const INVOKE_PREFIX: u64 = 0x696e766f6b65;
+
+/// Decimals of the fee token (ETH) of v1 transactions
+const FEE_DECIMALS: usize = 18;
+
+/// INVOKE v1 transaction
+///
+/// Encoded as a sequence of felts:
+/// sender_address, max_fee, nonce, chain_id, calldata_len, calldata...
+pub struct InvokeV1<'b> {
+    pub sender_address: &'b [u8; FELT_SIZE],
+    pub max_fee: &'b [u8; FELT_SIZE],
+    pub nonce: &'b [u8; FELT_SIZE],
+    pub chain_id: &'b [u8; FELT_SIZE],
+    pub calldata: ExecuteCalldata<'b>,
+}
+
+impl<'b> InvokeV1<'b> {
+    pub const VERSION: u64 = 1;
+
+    #[inline(never)]
+    pub fn parse(data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let sender_address = reader.felt()?;
+        let max_fee = reader.felt()?;
+        let nonce = reader.felt()?;
+        let chain_id = reader.felt()?;
+        let calldata = ExecuteCalldata::new(reader.array()?, chain_id);
+
+        let tx = Self {
+            sender_address,
+            max_fee,
+            nonce,
+            chain_id,
+            calldata,
+        };
+
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    /// Computes the transaction hash
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let calldata_hash = compute_hash_on_elements(felts_iter(self.calldata.raw()).map(to_felt));
+
+        let mut hasher = PedersenHasher::default();
+        hasher.update(&Felt::from_u64(INVOKE_PREFIX));
+        hasher.update(&Felt::from_u64(Self::VERSION));
+        hasher.update(&to_felt(self.sender_address));
+        //entry_point_selector
+        hasher.update(&Felt::ZERO);
+        hasher.update(&calldata_hash);
+        hasher.update(&to_felt(self.max_fee));
+        hasher.update(&to_felt(self.chain_id));
+        hasher.update(&to_felt(self.nonce));
+
+        hasher.finalize()
+    }
+}
+
+impl<'b> DisplayableItem for InvokeV1<'b> {
+    fn num_items(&self) -> usize {
+        4 + self.calldata.num_items()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        match item_n {
+            0 => {
+                render_title(pic_str!(b"Sender"), title);
+                render_address(self.sender_address, message, page)
+            }
+            1 => {
+                render_title(pic_str!(b"Max Fee"), title);
+                render_amount(
+                    &U256::from_be_bytes(self.max_fee),
+                    FEE_DECIMALS,
+                    pic_str!(b"ETH"),
+                    message,
+                    page,
+                )
+            }
+            2 => {
+                render_title(pic_str!(b"Nonce"), title);
+                render_felt_decimal(self.nonce, message, page)
+            }
+            3 => render_network(self.chain_id, title, message, page),
+            n => self.calldata.render_item(n - 4, title, message, page),
+        }
+    }
+}
+
+/// INVOKE v3 transaction
+///
+/// Encoded as a sequence of felts:
+/// sender_address, [`CommonFieldsV3`],
+/// account_deployment_data_len, account_deployment_data...,
+/// calldata_len, calldata...
+pub struct InvokeV3<'b> {
+    pub sender_address: &'b [u8; FELT_SIZE],
+    pub common: CommonFieldsV3<'b>,
+    pub account_deployment_data: &'b [u8],
+    pub calldata: ExecuteCalldata<'b>,
+}
+
+impl<'b> InvokeV3<'b> {
+    pub const VERSION: u64 = CommonFieldsV3::VERSION;
+
+    /// Parses the transaction, with L1 data gas bounds if `with_l1_data_gas`
+    #[inline(never)]
+    pub fn parse(data: &'b [u8], with_l1_data_gas: bool) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let sender_address = reader.felt()?;
+        let common = CommonFieldsV3::parse(&mut reader, with_l1_data_gas)?;
+        let account_deployment_data = reader.array()?;
+        let calldata = ExecuteCalldata::new(reader.array()?, common.chain_id);
+
+        let tx = Self {
+            sender_address,
+            common,
+            account_deployment_data,
+            calldata,
+        };
+
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    pub fn account_deployment_data_len(&self) -> usize {
+        self.account_deployment_data.len() / FELT_SIZE
+    }
+
+    /// Computes the transaction hash
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let mut hasher = PoseidonHasher::default();
+        hasher.update(&Felt::from_u64(INVOKE_PREFIX));
+        hasher.update(&Felt::from_u64(Self::VERSION));
+        hasher.update(&to_felt(self.sender_address));
+        self.common.hash_into(&mut hasher);
+        hasher.update(&poseidon_hash_many(
+            felts_iter(self.account_deployment_data).map(to_felt),
+        ));
+        hasher.update(&poseidon_hash_many(
+            felts_iter(self.calldata.raw()).map(to_felt),
+        ));
+
+        hasher.finalize()
+    }
+}
+
+impl<'b> DisplayableItem for InvokeV3<'b> {
+    fn num_items(&self) -> usize {
+        1 + self.common.num_items() + self.account_deployment_data_len() + self.calldata.num_items()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        if item_n == 0 {
+            render_title(pic_str!(b"Sender"), title);
+            return render_address(self.sender_address, message, page);
+        }
+
+        let mut idx = item_n as usize - 1;
+        if idx < self.common.num_items() {
+            return self.common.render_item(idx as u8, title, message, page);
+        }
+        idx -= self.common.num_items();
+
+        if idx < self.account_deployment_data_len() {
+            let felt = felts_iter(self.account_deployment_data)
+                .nth(idx)
+                .ok_or(ViewError::NoData)?;
+
+            render_title(pic_str!(b"Deployment Data"), title);
+            return render_felt(felt, message, page);
+        }
+        idx -= self.account_deployment_data_len();
+
+        self.calldata.render_item(idx as u8, title, message, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERIALIZED: &str = "04a2d5a3e5d5ab7ca6d4b0c37d2f1d0e9c6ca0dd1e2c8a8c4d3d4b5c6e7f8091000000000000000000000000000000000000000000000000002386f26fc10000000000000000000000000000000000000000000000000000000000000000000500000000000000000000000000000000000000000000000000534e5f4d41494e00000000000000000000000000000000000000000000000000000000000000070000000000000000000000000000000000000000000000000000000000000001049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc70083afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e0000000000000000000000000000000000000000000000000000000000000003000000000000000000000000000000000000000000000000000000000000012300000000000000000000000000000000000000000000000000000000000003e80000000000000000000000000000000000000000000000000000000000000000";
+
+    const EXPECTED_HASH: &str = "0305f34747e31b11c1a9f21f455fa6eb526538c2dc93ec87853a4d2718181745";
+
+    const SERIALIZED_V3: &str = "04a2d5a3e5d5ab7ca6d4b0c37d2f1d0e9c6ca0dd1e2c8a8c4d3d4b5c6e7f8091000000000000000000000000000000000000000000000000000000000000000700000000000000000000000000000000000000000000534e5f5345504f4c4941000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000186a000000000000000000000000000000000000000000000000000005af3107a40000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000007000000000000000000000000000000000000000000000000000000000000000104718f5a0fc34cc1af16a1cdee98ffb20c31f5cd61d6ab07201858f4287c938d0083afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e0000000000000000000000000000000000000000000000000000000000000003000000000000000000000000000000000000000000000000000000000000012300000000000000000000000000000000000000000000000000000000000003e80000000000000000000000000000000000000000000000000000000000000000";
+
+    const EXPECTED_HASH_V3: &str =
+        "0485c4f503d27f481e2fae83436bcbb74050e70afedf7b55f272820ba963eb6c";
+
+    #[test]
+    fn parse_and_hash() {
+        let data = hex::decode(SERIALIZED).unwrap();
+        let tx = InvokeV1::parse(&data).unwrap();
+
+        assert_eq!(tx.calldata.len(), 7);
+        //a single ETH transfer
+        assert_eq!(tx.calldata.calls().unwrap().n_calls(), 1);
+        assert_eq!(tx.num_items(), 4 + 1);
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        tx.render_item(4, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"[1/1] Transfer\0"));
+        assert!(message.starts_with(b"Send 0.000000000000001 ETH to 0x0000"));
+        assert_eq!(hex::encode(tx.hash().to_be_bytes()), EXPECTED_HASH);
+    }
+
+    #[test]
+    fn malformed() {
+        let data = hex::decode(SERIALIZED).unwrap();
+
+        //missing the last calldata item
+        let err = InvokeV1::parse(&data[..data.len() - FELT_SIZE])
+            .err()
+            .unwrap();
+        assert_eq!(err, ParserError::UnexpectedBufferEnd);
+
+        //extra data at the end
+        let mut extra = data.clone();
+        extra.extend_from_slice(&[0; FELT_SIZE]);
+        let err = InvokeV1::parse(&extra).err().unwrap();
+        assert_eq!(err, ParserError::UnexpectedData);
+
+        //sender address not a valid felt
+        let mut invalid = data;
+        invalid[..FELT_SIZE].copy_from_slice(&[0xFF; FELT_SIZE]);
+        let err = InvokeV1::parse(&invalid).err().unwrap();
+        assert_eq!(err, ParserError::InvalidFelt);
+    }
+
+    #[test]
+    fn parse_and_hash_v3() {
+        let data = hex::decode(SERIALIZED_V3).unwrap();
+        let tx = InvokeV3::parse(&data, false).unwrap();
+
+        assert_eq!(tx.common.tip, 0);
+        assert_eq!(tx.common.l1_gas.max_amount, 100_000);
+        assert_eq!(tx.common.paymaster_data_len(), 0);
+        assert_eq!(tx.calldata.len(), 7);
+        //a single STRK transfer
+        assert_eq!(tx.num_items(), 1 + 5 + 1);
+        assert_eq!(hex::encode(tx.hash().to_be_bytes()), EXPECTED_HASH_V3);
+    }
+
+    #[test]
+    fn parse_and_hash_v3_l1_data_gas() {
+        //the same transaction, with L1 data gas bounds after the L2 gas bounds
+        let mut data = hex::decode(SERIALIZED_V3).unwrap();
+        let mut bounds = [0; 2 * FELT_SIZE];
+        bounds[FELT_SIZE - 8..FELT_SIZE].copy_from_slice(&10_000u64.to_be_bytes());
+        bounds[2 * FELT_SIZE - 16..].copy_from_slice(&1_000_000_000u128.to_be_bytes());
+        data.splice(8 * FELT_SIZE..8 * FELT_SIZE, bounds.iter().copied());
+
+        //without the flag the bounds are taken for the next fields
+        assert!(InvokeV3::parse(&data, false).is_err());
+
+        let tx = InvokeV3::parse(&data, true).unwrap();
+        let l1_data_gas = tx.common.l1_data_gas.unwrap();
+        assert_eq!(l1_data_gas.max_amount, 10_000);
+        assert_eq!(l1_data_gas.max_price_per_unit, 1_000_000_000);
+        assert_eq!(tx.num_items(), 1 + 6 + 1);
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        tx.render_item(3, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"Max Fee L1 Data\0"));
+        assert!(message.starts_with(b"0.00001 STRK\0"));
+
+        assert_eq!(
+            hex::encode(tx.hash().to_be_bytes()),
+            "06127465eb22f529e40a739ca4f49a79f907316873c04bb2ec0196bf44fa616b"
+        );
+    }
+
+    #[test]
+    fn malformed_v3() {
+        let data = hex::decode(SERIALIZED_V3).unwrap();
+
+        //l1 gas max_amount doesn't fit in 64 bits
+        let mut invalid = data.clone();
+        invalid[5 * FELT_SIZE - 9] = 1;
+        let err = InvokeV3::parse(&invalid, false).err().unwrap();
+        assert_eq!(err, ParserError::ValueOutOfRange);
+
+        //unknown data availability mode
+        let mut invalid = data;
+        invalid[9 * FELT_SIZE - 1] = 2;
+        let err = InvokeV3::parse(&invalid, false).err().unwrap();
+        assert_eq!(err, ParserError::ValueOutOfRange);
+    }
+}
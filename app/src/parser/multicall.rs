@@ -0,0 +1,473 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Decoding of the calldata of account `__execute__` calls
+//!
+//! Two layouts are supported:
+//! * Cairo 1 `Array<Call>`:
+//!   calls_len, (to, selector, calldata_len, calldata...)...
+//! * Cairo 0 legacy:
+//!   call_array_len, (to, selector, data_offset, data_len)..., calldata_len, calldata...
+//!
+//! Which one applies depends on the account, unknown to the app, so calldata valid
+//! in both layouts is not decoded: the calls shown could differ from the ones executed
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::handlers::handle_ui_message;
+
+use super::{
+    felt_to_u64, felts_iter, render_address, render_felt, render_selector, render_title,
+    DisplayableItem, FeltReader, Network, ParserError, TextBuffer, TokenOperation,
+    TokenOperationKind, FELT_SIZE,
+};
+
+/// A single call made by the account
+pub struct Call<'b> {
+    pub to: &'b [u8; FELT_SIZE],
+    pub selector: &'b [u8; FELT_SIZE],
+    pub calldata: &'b [u8],
+}
+
+impl<'b> Call<'b> {
+    pub fn calldata_len(&self) -> usize {
+        self.calldata.len() / FELT_SIZE
+    }
+
+    /// Contract, entrypoint and each argument
+    pub fn num_items(&self) -> usize {
+        2 + self.calldata_len()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Layout<'b> {
+    Cairo0 {
+        call_array: &'b [u8],
+        calldata: &'b [u8],
+    },
+    Cairo1 {
+        calls: &'b [u8],
+    },
+}
+
+/// The list of calls of an `__execute__` invocation
+pub struct Multicall<'b> {
+    layout: Layout<'b>,
+    n_calls: usize,
+    /// Used to recognize calls to known token contracts
+    network: Option<Network>,
+}
+
+/// Number of felts of each entry of the legacy call array
+const CAIRO0_CALL_SIZE: usize = 4;
+
+impl<'b> Multicall<'b> {
+    /// Decodes `data` as the calldata of `__execute__`, in whichever layout is valid
+    ///
+    /// Returns [`ParserError::Ambiguous`] if both layouts are valid
+    ///
+    /// `network` is where the transaction is to be executed, if known
+    #[inline(never)]
+    pub fn parse(data: &'b [u8], network: Option<Network>) -> Result<Self, ParserError> {
+        let layout = match (Self::parse_cairo1(data), Self::parse_cairo0(data)) {
+            (Ok(_), Ok(_)) => return Err(ParserError::Ambiguous),
+            (Ok(layout), Err(_)) | (Err(_), Ok(layout)) => layout,
+            (Err(e), Err(_)) => return Err(e),
+        };
+
+        Ok(Self {
+            n_calls: layout.0,
+            layout: layout.1,
+            network,
+        })
+    }
+
+    fn parse_cairo1(data: &'b [u8]) -> Result<(usize, Layout<'b>), ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let n_calls = reader.length()?;
+        let calls = reader.remaining();
+        for _ in 0..n_calls {
+            reader.felt()?;
+            reader.felt()?;
+            reader.array()?;
+        }
+        reader.finish()?;
+
+        Ok((n_calls, Layout::Cairo1 { calls }))
+    }
+
+    fn parse_cairo0(data: &'b [u8]) -> Result<(usize, Layout<'b>), ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let n_calls = reader.length()?;
+        let call_array = reader.felts(n_calls * CAIRO0_CALL_SIZE)?;
+        let calldata = reader.array()?;
+        reader.finish()?;
+
+        let calldata_len = calldata.len() / FELT_SIZE;
+        for entry in call_array.chunks_exact(CAIRO0_CALL_SIZE * FELT_SIZE) {
+            let (offset, len) = Self::cairo0_data_range(entry)?;
+            if offset + len > calldata_len {
+                return Err(ParserError::ValueOutOfRange);
+            }
+        }
+
+        Ok((
+            n_calls,
+            Layout::Cairo0 {
+                call_array,
+                calldata,
+            },
+        ))
+    }
+
+    /// Reads data_offset and data_len of a legacy call array entry
+    fn cairo0_data_range(entry: &[u8]) -> Result<(usize, usize), ParserError> {
+        let mut fields = felts_iter(entry).skip(2).map(|felt| {
+            felt_to_u64(felt)
+                .filter(|&n| n <= u16::MAX as u64)
+                .map(|n| n as usize)
+                .ok_or(ParserError::ValueOutOfRange)
+        });
+
+        //entries are always 4 felts long
+        match (fields.next(), fields.next()) {
+            (Some(offset), Some(len)) => Ok((offset?, len?)),
+            _ => Err(ParserError::UnexpectedBufferEnd),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn n_calls(&self) -> usize {
+        self.n_calls
+    }
+
+    /// Iterate over the calls
+    pub fn calls(&self) -> impl Iterator<Item = Call<'b>> {
+        let layout = self.layout;
+        let mut reader = match layout {
+            Layout::Cairo1 { calls } => FeltReader::new(calls),
+            Layout::Cairo0 { .. } => FeltReader::new(&[]),
+        };
+
+        (0..self.n_calls).filter_map(move |i| match layout {
+            //all the calls have been validated when parsing
+            Layout::Cairo1 { .. } => Some(Call {
+                to: reader.felt().ok()?,
+                selector: reader.felt().ok()?,
+                calldata: reader.array().ok()?,
+            }),
+            Layout::Cairo0 {
+                call_array,
+                calldata,
+            } => {
+                let size = CAIRO0_CALL_SIZE * FELT_SIZE;
+                let entry = call_array.get(i * size..(i + 1) * size)?;
+                let (offset, len) = Self::cairo0_data_range(entry).ok()?;
+                let mut fields = felts_iter(entry);
+
+                Some(Call {
+                    to: fields.next()?,
+                    selector: fields.next()?,
+                    calldata: calldata.get(offset * FELT_SIZE..(offset + len) * FELT_SIZE)?,
+                })
+            }
+        })
+    }
+}
+
+/// Writes "[call/n_calls] label" to `title`, followed by `arg` if present
+fn render_call_title(
+    call: usize,
+    n_calls: usize,
+    label: &[u8],
+    arg: Option<usize>,
+    title: &mut [u8],
+) {
+    let mut text = TextBuffer::<40>::new();
+    text.push(pic_str!(b"["))
+        .push_u64(call as u64 + 1)
+        .push(pic_str!(b"/"))
+        .push_u64(n_calls as u64)
+        .push(pic_str!(b"] "))
+        .push(label);
+    if let Some(arg) = arg {
+        text.push(pic_str!(b" ")).push_u64(arg as u64 + 1);
+    }
+
+    render_title(text.as_bytes(), title);
+}
+
+impl<'b> Multicall<'b> {
+    /// Returns the call as a token operation, if it's a transfer or approval of a known token
+    fn token_operation(&self, call: &Call<'b>) -> Option<TokenOperation<'b>> {
+        TokenOperation::decode(call, self.network?)
+    }
+
+    /// Token operations are shown as a single item
+    fn call_items(&self, call: &Call<'b>) -> usize {
+        if self.token_operation(call).is_some() {
+            1
+        } else {
+            call.num_items()
+        }
+    }
+}
+
+impl<'b> DisplayableItem for Multicall<'b> {
+    fn num_items(&self) -> usize {
+        self.calls().map(|call| self.call_items(&call)).sum()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        let mut item_n = item_n as usize;
+
+        for (i, call) in self.calls().enumerate() {
+            let call_items = self.call_items(&call);
+            if item_n >= call_items {
+                item_n -= call_items;
+                continue;
+            }
+
+            if let Some(op) = self.token_operation(&call) {
+                let label = match op.kind {
+                    TokenOperationKind::Transfer => pic_str!(b"Transfer"),
+                    TokenOperationKind::Approve => pic_str!(b"Approve"),
+                };
+
+                render_call_title(i, self.n_calls, label, None, title);
+                return op.render(message, page);
+            }
+
+            return match item_n {
+                0 => {
+                    render_call_title(i, self.n_calls, pic_str!(b"Contract"), None, title);
+                    render_address(call.to, message, page)
+                }
+                1 => {
+                    render_call_title(i, self.n_calls, pic_str!(b"Entrypoint"), None, title);
+                    render_selector(call.selector, message, page)
+                }
+                n => {
+                    let arg = n - 2;
+                    let felt = felts_iter(call.calldata)
+                        .nth(arg)
+                        .ok_or(ViewError::NoData)?;
+
+                    render_call_title(i, self.n_calls, pic_str!(b"Arg"), Some(arg), title);
+                    render_felt(felt, message, page)
+                }
+            };
+        }
+
+        Err(ViewError::NoData)
+    }
+}
+
+/// Calldata of `__execute__`, displayed as individual calls when it can be decoded
+/// and as raw felts otherwise, preceded by a warning if it's ambiguous
+pub struct ExecuteCalldata<'b> {
+    raw: &'b [u8],
+    calls: Option<Multicall<'b>>,
+    ambiguous: bool,
+}
+
+impl<'b> ExecuteCalldata<'b> {
+    /// `raw` is expected to be an array previously read with [`FeltReader`]
+    /// and `chain_id` the one of the transaction
+    pub fn new(raw: &'b [u8], chain_id: &[u8; FELT_SIZE]) -> Self {
+        let calls = Multicall::parse(raw, Network::from_chain_id(chain_id));
+
+        Self {
+            raw,
+            ambiguous: calls.as_ref().err() == Some(&ParserError::Ambiguous),
+            calls: calls.ok(),
+        }
+    }
+
+    pub fn raw(&self) -> &'b [u8] {
+        self.raw
+    }
+
+    pub fn len(&self) -> usize {
+        self.raw.len() / FELT_SIZE
+    }
+
+    #[cfg(test)]
+    pub fn calls(&self) -> Option<&Multicall<'b>> {
+        self.calls.as_ref()
+    }
+
+    /// Returns true if the calldata is valid in both layouts, see the module documentation
+    #[cfg(test)]
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguous
+    }
+}
+
+impl<'b> DisplayableItem for ExecuteCalldata<'b> {
+    fn num_items(&self) -> usize {
+        match &self.calls {
+            Some(calls) => calls.num_items(),
+            None => self.ambiguous as usize + self.len(),
+        }
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        if let Some(calls) = &self.calls {
+            return calls.render_item(item_n, title, message, page);
+        }
+
+        let mut item_n = item_n as usize;
+        if self.ambiguous {
+            if item_n == 0 {
+                render_title(pic_str!(b"Warning"), title);
+                return handle_ui_message(pic_str!(b"Ambiguous calldata"), message, page);
+            }
+            item_n -= 1;
+        }
+
+        let felt = felts_iter(self.raw).nth(item_n).ok_or(ViewError::NoData)?;
+
+        render_title(pic_str!(b"Calldata"), title);
+        render_felt(felt, message, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    fn felts(values: &[u64]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|&v| {
+                let mut felt = [0; FELT_SIZE];
+                felt[FELT_SIZE - 8..].copy_from_slice(&v.to_be_bytes());
+                felt
+            })
+            .collect()
+    }
+
+    fn title(calls: &Multicall, item_n: u8) -> std::string::String {
+        let mut title = [0; 40];
+        let mut message = [0; 100];
+        calls
+            .render_item(item_n, &mut title, &mut message, 0)
+            .unwrap();
+
+        let len = title.iter().position(|&b| b == 0).unwrap();
+        std::str::from_utf8(&title[..len]).unwrap().into()
+    }
+
+    #[test]
+    fn cairo1() {
+        //approve(spender, amount) then swap(a, b, c)
+        let data = felts(&[2, 0xaa, 0x1, 3, 0x10, 0x20, 0x0, 0xbb, 0x2, 3, 1, 2, 3]);
+        let calls = Multicall::parse(&data, None).unwrap();
+
+        assert_eq!(calls.n_calls(), 2);
+        assert_eq!(calls.num_items(), 5 + 5);
+
+        let parsed: Vec<_> = calls.calls().collect();
+        assert_eq!(parsed[0].to[FELT_SIZE - 1], 0xaa);
+        assert_eq!(parsed[1].selector[FELT_SIZE - 1], 0x2);
+        assert_eq!(parsed[1].calldata, &felts(&[1, 2, 3])[..]);
+
+        assert_eq!(title(&calls, 0), "[1/2] Contract");
+        assert_eq!(title(&calls, 4), "[1/2] Arg 3");
+        assert_eq!(title(&calls, 6), "[2/2] Entrypoint");
+    }
+
+    #[test]
+    fn cairo0() {
+        //2 calls, the first with 1 argument and the second with 2
+        let data = felts(&[2, 0xaa, 0x1, 0, 1, 0xbb, 0x2, 1, 2, 3, 7, 8, 9]);
+        let calls = Multicall::parse(&data, None).unwrap();
+
+        assert_eq!(calls.n_calls(), 2);
+        assert_eq!(calls.num_items(), 3 + 4);
+
+        let parsed: Vec<_> = calls.calls().collect();
+        assert_eq!(parsed[0].calldata, &felts(&[7])[..]);
+        assert_eq!(parsed[1].to[FELT_SIZE - 1], 0xbb);
+        assert_eq!(parsed[1].calldata, &felts(&[8, 9])[..]);
+
+        //the second call's data goes past the end of calldata
+        let data = felts(&[2, 0xaa, 0x1, 0, 1, 0xbb, 0x2, 1, 3, 3, 7, 8, 9]);
+        assert!(Multicall::parse(&data, None).is_err());
+    }
+
+    #[test]
+    fn raw_fallback() {
+        let data = felts(&[5, 1, 2]);
+        let calldata = ExecuteCalldata::new(&data, &[0; FELT_SIZE]);
+
+        assert!(calldata.calls().is_none());
+        assert!(!calldata.is_ambiguous());
+        assert_eq!(calldata.num_items(), 3);
+    }
+
+    #[test]
+    fn ambiguous() {
+        //Cairo 1: T.transfer() and a call to contract 3 with 3 arguments
+        //Cairo 0: T.transfer(R, lo, hi)
+        let (t, transfer, r, lo, hi) = (0xaa, 0x83af, 0xbb, 100, 0);
+        let data = felts(&[2, t, transfer, 0, 3, r, 6, 0, 0, 3, r, lo, hi]);
+        assert!(Multicall::parse_cairo1(&data).is_ok());
+        assert!(Multicall::parse_cairo0(&data).is_ok());
+
+        assert_eq!(
+            Multicall::parse(&data, None).err(),
+            Some(ParserError::Ambiguous)
+        );
+
+        let calldata = ExecuteCalldata::new(&data, &[0; FELT_SIZE]);
+        assert!(calldata.calls().is_none());
+        assert!(calldata.is_ambiguous());
+        assert_eq!(calldata.num_items(), 1 + 13);
+
+        let (mut title, mut message) = ([0; 40], [0; 100]);
+        calldata
+            .render_item(0, &mut title, &mut message, 0)
+            .unwrap();
+        assert!(title.starts_with(b"Warning\0"));
+        assert!(message.starts_with(b"Ambiguous calldata\0"));
+
+        calldata
+            .render_item(1, &mut title, &mut message, 0)
+            .unwrap();
+        assert!(title.starts_with(b"Calldata\0"));
+        assert!(message.starts_with(b"2\0"));
+    }
+}
@@ -0,0 +1,82 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use super::{felt_to_u128, render_short_string, render_title, FELT_SIZE};
+
+/// "SN_MAIN" as a short string
+const SN_MAIN: u128 = 0x534e5f4d41494e;
+
+/// "SN_SEPOLIA" as a short string
+const SN_SEPOLIA: u128 = 0x534e5f5345504f4c4941;
+
+/// Starknet networks known by the app
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum Network {
+    Mainnet,
+    Sepolia,
+}
+
+impl Network {
+    /// Returns the network identified by `chain_id`, if known
+    pub fn from_chain_id(chain_id: &[u8; FELT_SIZE]) -> Option<Self> {
+        match felt_to_u128(chain_id)? {
+            SN_MAIN => Some(Self::Mainnet),
+            SN_SEPOLIA => Some(Self::Sepolia),
+            _ => None,
+        }
+    }
+}
+
+/// Renders the chain id, decoded as short string, as the "Network" item
+#[inline(never)]
+pub fn render_network(
+    chain_id: &[u8; FELT_SIZE],
+    title: &mut [u8],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    render_title(pic_str!(b"Network"), title);
+    render_short_string(chain_id, message, page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(chain_id: u128) -> std::vec::Vec<u8> {
+        let mut felt = [0; FELT_SIZE];
+        felt[FELT_SIZE - 16..].copy_from_slice(&chain_id.to_be_bytes());
+
+        let (mut title, mut message) = ([0; 40], [0; 100]);
+        render_network(&felt, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"Network\0"));
+
+        let len = message.iter().position(|&c| c == 0).unwrap();
+        message[..len].to_vec()
+    }
+
+    #[test]
+    fn network_item() {
+        assert_eq!(render(SN_MAIN), b"SN_MAIN");
+        assert_eq!(render(SN_SEPOLIA), b"SN_SEPOLIA");
+
+        //not printable, shown as hex
+        assert_eq!(render(0x1f), std::format!("0x{:064x}", 0x1f).as_bytes());
+    }
+}
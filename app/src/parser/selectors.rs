@@ -0,0 +1,266 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Well known entrypoints, to show their name instead of the raw selector
+//!
+//! Selectors are the `starknet_keccak` of the entrypoint name
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::{
+    handlers::handle_ui_message,
+    utils::{hex_encode, ApduPanic},
+};
+
+use super::FELT_SIZE;
+
+struct KnownSelector {
+    selector: [u8; FELT_SIZE],
+    name: &'static [u8],
+}
+
+#[rustfmt::skip]
+const KNOWN_SELECTORS: [KnownSelector; 24] = [
+    KnownSelector {
+        name: b"transfer",
+        selector: [
+            0x00, 0x83, 0xaf, 0xd3, 0xf4, 0xca, 0xed, 0xc6, 0xee, 0xbf, 0x44, 0x24, 0x6f, 0xe5, 0x4e, 0x38,
+            0xc9, 0x5e, 0x31, 0x79, 0xa5, 0xec, 0x9e, 0xa8, 0x17, 0x40, 0xec, 0xa5, 0xb4, 0x82, 0xd1, 0x2e,
+        ],
+    },
+    KnownSelector {
+        name: b"transferFrom",
+        selector: [
+            0x00, 0x41, 0xb0, 0x33, 0xf4, 0xa3, 0x1d, 0xf8, 0x06, 0x7c, 0x24, 0xd1, 0xe9, 0xb5, 0x50, 0xa2,
+            0xce, 0x75, 0xfd, 0x4a, 0x29, 0xe1, 0x14, 0x7a, 0xf9, 0x75, 0x21, 0x74, 0xf0, 0xe6, 0xcb, 0x20,
+        ],
+    },
+    KnownSelector {
+        name: b"transfer_from",
+        selector: [
+            0x03, 0x70, 0x4f, 0xfe, 0x8f, 0xba, 0x16, 0x1b, 0xe0, 0xe9, 0x94, 0x95, 0x17, 0x51, 0xa5, 0x03,
+            0x3b, 0x14, 0x62, 0xb9, 0x18, 0xff, 0x78, 0x5c, 0x0a, 0x63, 0x6b, 0xe7, 0x18, 0xdf, 0xdb, 0x68,
+        ],
+    },
+    KnownSelector {
+        name: b"approve",
+        selector: [
+            0x02, 0x19, 0x20, 0x9e, 0x08, 0x32, 0x75, 0x17, 0x17, 0x74, 0xda, 0xb1, 0xdf, 0x80, 0x98, 0x2e,
+            0x9d, 0xf2, 0x09, 0x65, 0x16, 0xf0, 0x63, 0x19, 0xc5, 0xc6, 0xd7, 0x1a, 0xe0, 0xa8, 0x48, 0x0c,
+        ],
+    },
+    KnownSelector {
+        name: b"increaseAllowance",
+        selector: [
+            0x01, 0x6c, 0xc0, 0x63, 0xb8, 0x33, 0x83, 0x63, 0xcf, 0x38, 0x8c, 0xe7, 0xfe, 0x1d, 0xf4, 0x08,
+            0xbf, 0x10, 0xf1, 0x6c, 0xd5, 0x16, 0x35, 0xd3, 0x92, 0xe2, 0x1d, 0x85, 0x2f, 0xaf, 0xb6, 0x83,
+        ],
+    },
+    KnownSelector {
+        name: b"increase_allowance",
+        selector: [
+            0x01, 0xd1, 0x3a, 0xb0, 0xa7, 0x6d, 0x74, 0x07, 0xb1, 0xd5, 0xfa, 0xcc, 0xd4, 0xb3, 0xd8, 0xa9,
+            0xef, 0xe4, 0x2f, 0x3d, 0x3c, 0x21, 0x76, 0x64, 0x31, 0xd4, 0xfa, 0xfb, 0x30, 0xf4, 0x5b, 0xd4,
+        ],
+    },
+    KnownSelector {
+        name: b"decreaseAllowance",
+        selector: [
+            0x01, 0xaa, 0xf3, 0xe6, 0x10, 0x7d, 0xd1, 0x34, 0x9c, 0x81, 0x54, 0x3f, 0xf4, 0x22, 0x1a, 0x32,
+            0x68, 0x14, 0xf7, 0x7d, 0xad, 0xcc, 0x58, 0x10, 0x80, 0x7b, 0x74, 0xf1, 0xa4, 0x9d, 0xed, 0x4e,
+        ],
+    },
+    KnownSelector {
+        name: b"decrease_allowance",
+        selector: [
+            0x03, 0xb0, 0x76, 0x18, 0x6c, 0x19, 0xfe, 0x96, 0x22, 0x1e, 0x4d, 0xfa, 0xcd, 0x40, 0xc5, 0x19,
+            0xf6, 0x12, 0xea, 0xe0, 0x2e, 0x05, 0x55, 0xe4, 0xe1, 0x15, 0xa2, 0xa6, 0xcf, 0x2f, 0x1c, 0x1f,
+        ],
+    },
+    KnownSelector {
+        name: b"multicall",
+        selector: [
+            0x02, 0x04, 0x62, 0x91, 0x14, 0xd2, 0x53, 0xb5, 0x1e, 0x37, 0x64, 0xb5, 0x21, 0xf7, 0x93, 0x71,
+            0x3a, 0xc9, 0xd8, 0x04, 0xdb, 0x93, 0x3e, 0x83, 0x98, 0x57, 0xe0, 0x22, 0xb5, 0xfe, 0x41, 0x50,
+        ],
+    },
+    KnownSelector {
+        name: b"swap",
+        selector: [
+            0x01, 0x55, 0x43, 0xc3, 0x70, 0x86, 0x53, 0xcd, 0xa9, 0xd4, 0x18, 0xb4, 0xcc, 0xd3, 0xbe, 0x11,
+            0x36, 0x8e, 0x40, 0x63, 0x6c, 0x10, 0xc4, 0x4b, 0x18, 0xcf, 0xe7, 0x56, 0xb6, 0xd8, 0x8b, 0x29,
+        ],
+    },
+    KnownSelector {
+        name: b"multi_route_swap",
+        selector: [
+            0x01, 0x17, 0x15, 0x93, 0xaa, 0x5b, 0xda, 0xdd, 0xa4, 0xd6, 0xb0, 0xef, 0xde, 0x6c, 0xc9, 0x4e,
+            0xe7, 0x64, 0x9c, 0x31, 0x63, 0xd5, 0xef, 0xeb, 0x19, 0xda, 0x6c, 0x16, 0xd6, 0x3a, 0x2a, 0x63,
+        ],
+    },
+    KnownSelector {
+        name: b"swap_exact_tokens_for_tokens",
+        selector: [
+            0x03, 0x27, 0x68, 0x61, 0xcf, 0x5e, 0x05, 0xd6, 0xda, 0xf8, 0xf3, 0x52, 0xca, 0xbb, 0x47, 0xdf,
+            0x62, 0x3e, 0xb1, 0x0c, 0x38, 0x3a, 0xb7, 0x42, 0xfc, 0xc7, 0xab, 0xea, 0x94, 0xd5, 0xc5, 0xcc,
+        ],
+    },
+    KnownSelector {
+        name: b"deposit",
+        selector: [
+            0x00, 0xc7, 0x3f, 0x68, 0x11, 0x76, 0xfc, 0x7b, 0x3f, 0x96, 0x93, 0x98, 0x6f, 0xd7, 0xb1, 0x45,
+            0x81, 0xe8, 0xd5, 0x40, 0x51, 0x9e, 0x27, 0x40, 0x0e, 0x88, 0xb8, 0x71, 0x39, 0x32, 0xbe, 0x01,
+        ],
+    },
+    KnownSelector {
+        name: b"withdraw",
+        selector: [
+            0x01, 0x55, 0x11, 0xcc, 0x36, 0x94, 0xf6, 0x43, 0x79, 0x90, 0x84, 0x37, 0xd6, 0xd6, 0x44, 0x58,
+            0xdc, 0x76, 0xd0, 0x24, 0x82, 0x05, 0x2b, 0xfb, 0x8a, 0x5b, 0x33, 0xa7, 0x2c, 0x05, 0x4c, 0x77,
+        ],
+    },
+    KnownSelector {
+        name: b"mint",
+        selector: [
+            0x02, 0xf0, 0xb3, 0xc5, 0x71, 0x03, 0x79, 0x60, 0x9e, 0xb5, 0x49, 0x5f, 0x1e, 0xcd, 0x34, 0x8c,
+            0xb2, 0x81, 0x67, 0x71, 0x1b, 0x73, 0x60, 0x9f, 0xe5, 0x65, 0xa7, 0x27, 0x34, 0x55, 0x03, 0x54,
+        ],
+    },
+    KnownSelector {
+        name: b"burn",
+        selector: [
+            0x00, 0x3e, 0x8c, 0xfd, 0x47, 0x25, 0xc1, 0xe2, 0x8f, 0xa4, 0xa6, 0xe3, 0xe4, 0x68, 0xb4, 0xfc,
+            0xf7, 0x53, 0x67, 0x16, 0x6b, 0x85, 0x0a, 0xc5, 0xf0, 0x4e, 0x33, 0xec, 0x84, 0x3e, 0x82, 0xc1,
+        ],
+    },
+    KnownSelector {
+        name: b"stake",
+        selector: [
+            0x03, 0xa0, 0x47, 0x95, 0xac, 0xcb, 0x4b, 0x73, 0xd1, 0x2f, 0x13, 0xb0, 0x5a, 0x1e, 0x0e, 0x24,
+            0x0c, 0xef, 0xeb, 0x9a, 0x89, 0xd0, 0x08, 0x67, 0x67, 0x30, 0x86, 0x7a, 0x81, 0x9d, 0x2f, 0x79,
+        ],
+    },
+    KnownSelector {
+        name: b"unstake",
+        selector: [
+            0x02, 0x3e, 0x3d, 0xfd, 0xfc, 0xe4, 0x65, 0xcb, 0x27, 0xb5, 0xe8, 0xc8, 0x96, 0xae, 0xde, 0x30,
+            0xa9, 0x67, 0x78, 0xc0, 0xa4, 0xff, 0x3c, 0x8e, 0x89, 0x2d, 0x8d, 0xc2, 0x75, 0x83, 0xe2, 0x2c,
+        ],
+    },
+    KnownSelector {
+        name: b"claim",
+        selector: [
+            0x00, 0xb7, 0x58, 0x36, 0x1d, 0x5e, 0x84, 0x38, 0x0e, 0xf1, 0xe6, 0x32, 0xf8, 0x9d, 0x8e, 0x76,
+            0xa8, 0x67, 0x7d, 0xbc, 0x3f, 0x4b, 0x93, 0xa4, 0xf9, 0xd7, 0x5d, 0x2a, 0x60, 0x48, 0xf3, 0x12,
+        ],
+    },
+    KnownSelector {
+        name: b"safeTransferFrom",
+        selector: [
+            0x01, 0x9d, 0x59, 0xd0, 0x13, 0xd4, 0xaa, 0x1a, 0x8b, 0x1c, 0xe4, 0xc8, 0x29, 0x90, 0x86, 0xf0,
+            0x70, 0x73, 0x3b, 0x45, 0x3c, 0x02, 0xd0, 0xdc, 0x46, 0xe7, 0x35, 0xed, 0xc0, 0x4d, 0x64, 0x44,
+        ],
+    },
+    KnownSelector {
+        name: b"safe_transfer_from",
+        selector: [
+            0x01, 0x6f, 0x02, 0x18, 0xb3, 0x3b, 0x5c, 0xf2, 0x73, 0x19, 0x67, 0x87, 0xd7, 0xcf, 0x13, 0x9a,
+            0x9a, 0xd1, 0x3d, 0x58, 0xe6, 0x67, 0x4d, 0xcd, 0xce, 0x72, 0x2b, 0x3b, 0xf8, 0x38, 0x98, 0x63,
+        ],
+    },
+    KnownSelector {
+        name: b"setApprovalForAll",
+        selector: [
+            0x02, 0xd4, 0xc8, 0xea, 0x4c, 0x8f, 0xb9, 0xf5, 0x71, 0xd1, 0xf6, 0xf9, 0xb7, 0x69, 0x2f, 0xff,
+            0x8e, 0x5c, 0xea, 0xf7, 0x3b, 0x1d, 0xf9, 0x8e, 0x7d, 0xa8, 0xc1, 0x10, 0x9b, 0x39, 0xae, 0x9a,
+        ],
+    },
+    KnownSelector {
+        name: b"set_approval_for_all",
+        selector: [
+            0x00, 0xd8, 0x6c, 0xa3, 0xd4, 0x16, 0x35, 0xe2, 0x0c, 0x18, 0x01, 0x81, 0x04, 0x6b, 0x11, 0xab,
+            0xcf, 0x19, 0xe1, 0xbd, 0xef, 0x3d, 0xca, 0xa4, 0xc1, 0x80, 0x30, 0x0c, 0xcc, 0xa1, 0x81, 0x3f,
+        ],
+    },
+    KnownSelector {
+        name: b"upgrade",
+        selector: [
+            0x00, 0xf2, 0xf7, 0xc1, 0x5c, 0xbe, 0x06, 0xc8, 0xd9, 0x45, 0x97, 0xcd, 0x91, 0xfd, 0x7f, 0x33,
+            0x69, 0xea, 0xe8, 0x42, 0x35, 0x92, 0x35, 0x71, 0x2d, 0xef, 0x55, 0x84, 0xf8, 0xd2, 0x70, 0xcd,
+        ],
+    },
+];
+
+/// Returns the name of the entrypoint with the given `selector`, if known
+pub fn selector_name(selector: &[u8; FELT_SIZE]) -> Option<&'static [u8]> {
+    let table = PIC::new(&KNOWN_SELECTORS).into_inner();
+
+    table
+        .iter()
+        .find(|known| &known.selector == selector)
+        .map(|known| PIC::new(known.name).into_inner())
+}
+
+/// Renders the name of the entrypoint if known,
+/// otherwise the selector as hex, marked as unknown
+#[inline(never)]
+pub fn render_selector(
+    selector: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    if let Some(name) = selector_name(selector) {
+        return handle_ui_message(name, message, page);
+    }
+
+    let prefix = pic_str!(b"Unknown entrypoint 0x");
+    let mut buf = [0; 32 + FELT_SIZE * 2];
+    buf[..prefix.len()].copy_from_slice(prefix);
+    //this is impossible that will error since the sizes are all checked
+    let len = hex_encode(&selector[..], &mut buf[prefix.len()..]).apdu_unwrap();
+
+    handle_ui_message(&buf[..prefix.len() + len], message, page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(s: &str) -> [u8; FELT_SIZE] {
+        let mut bytes = [0; FELT_SIZE];
+        bytes.copy_from_slice(&hex::decode(s).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn known() {
+        let transfer = selector("0083afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e");
+        assert_eq!(selector_name(&transfer), Some(&b"transfer"[..]));
+
+        let approve = selector("0219209e083275171774dab1df80982e9df2096516f06319c5c6d71ae0a8480c");
+        assert_eq!(selector_name(&approve), Some(&b"approve"[..]));
+    }
+
+    #[test]
+    fn unknown() {
+        let unknown = selector("0000000000000000000000000000000000000000000000000000000000001234");
+        assert_eq!(selector_name(&unknown), None);
+
+        let mut message = [0; 100];
+        render_selector(&unknown, &mut message, 0).unwrap();
+        assert!(message.starts_with(b"Unknown entrypoint 0x0000"));
+    }
+}
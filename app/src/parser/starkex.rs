@@ -0,0 +1,429 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! StarkEx limit orders and transfers
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::{
+    crypto::{
+        felt::{Felt, U256},
+        pedersen::pedersen_hash,
+    },
+    handlers::handle_ui_message,
+};
+
+use super::{
+    render_felt_hex, render_title, to_felt, DisplayableItem, FeltReader, ParserError, TextBuffer,
+    FELT_SIZE,
+};
+
+const VAULT_BITS: u32 = 31;
+const AMOUNT_BITS: u32 = 63;
+const NONCE_BITS: u32 = 31;
+const EXPIRATION_BITS: u32 = 22;
+
+/// Type of the instruction, the first field of the packed message
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+enum Instruction {
+    LimitOrder = 0,
+    Transfer = 1,
+    ConditionalTransfer = 2,
+}
+
+/// Reads a felt which must fit in `bits` bits
+fn read_bits(reader: &mut FeltReader<'_>, bits: u32) -> Result<u64, ParserError> {
+    let value = reader.u64()?;
+    if value >> bits != 0 {
+        return Err(ParserError::ValueOutOfRange);
+    }
+
+    Ok(value)
+}
+
+/// Appends `value` (smaller than 2^`bits`) to the packed message
+fn pack(packed: U256, bits: u32, value: u64) -> U256 {
+    let (shifted, _) = packed.overflowing_mul_small(1 << bits);
+    shifted.overflowing_add(&U256::from_u64(value)).0
+}
+
+/// Computes the message hash shared by all StarkEx instructions
+///
+/// pedersen(pedersen(token0, token1) [, condition], packed) with packed being
+/// instruction | vault0 | vault1 | amount0 | amount1 | nonce | expiration
+#[allow(clippy::too_many_arguments)]
+#[inline(never)]
+fn message_hash(
+    instruction: Instruction,
+    vaults: (u64, u64),
+    amounts: (u64, u64),
+    tokens: (&[u8; FELT_SIZE], &[u8; FELT_SIZE]),
+    condition: Option<&[u8; FELT_SIZE]>,
+    nonce: u64,
+    expiration: u64,
+) -> Felt {
+    let mut packed = U256::from_u64(instruction as u64);
+    packed = pack(packed, VAULT_BITS, vaults.0);
+    packed = pack(packed, VAULT_BITS, vaults.1);
+    packed = pack(packed, AMOUNT_BITS, amounts.0);
+    packed = pack(packed, AMOUNT_BITS, amounts.1);
+    packed = pack(packed, NONCE_BITS, nonce);
+    packed = pack(packed, EXPIRATION_BITS, expiration);
+
+    let mut hash = pedersen_hash(&to_felt(tokens.0), &to_felt(tokens.1));
+    if let Some(condition) = condition {
+        hash = pedersen_hash(&hash, &to_felt(condition));
+    }
+
+    //all the fields add up to 243 bits, so this is always a valid felt
+    pedersen_hash(&hash, &Felt::from_u256_reduced(packed))
+}
+
+fn push_two_digits<const N: usize>(text: &mut TextBuffer<N>, n: u64) -> &mut TextBuffer<N> {
+    if n < 10 {
+        text.push(pic_str!(b"0"));
+    }
+
+    text.push_u64(n)
+}
+
+/// Renders the expiration, in hours since the unix epoch, as a UTC date
+#[inline(never)]
+fn render_expiration(hours: u64, message: &mut [u8], page: u8) -> Result<u8, ViewError> {
+    //days since 1970-01-01 to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let z = hours / 24 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as u64;
+
+    let mut text = TextBuffer::<32>::new();
+    text.push_u64(year).push(pic_str!(b"-"));
+    push_two_digits(&mut text, month).push(pic_str!(b"-"));
+    push_two_digits(&mut text, day).push(pic_str!(b" "));
+    push_two_digits(&mut text, hours % 24).push(pic_str!(b":00 UTC"));
+
+    handle_ui_message(text.as_bytes(), message, page)
+}
+
+/// Renders "`amount` of `asset`"
+#[inline(never)]
+fn render_asset_amount(
+    amount: u64,
+    asset: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    let mut text = TextBuffer::<{ 20 + 4 + 2 + FELT_SIZE * 2 }>::new();
+    text.push_u64(amount)
+        .push(pic_str!(b" of "))
+        .push_felt_hex(asset);
+
+    handle_ui_message(text.as_bytes(), message, page)
+}
+
+fn render_u64(
+    label: &[u8],
+    value: u64,
+    title: &mut [u8],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    render_title(label, title);
+
+    let mut text = TextBuffer::<20>::new();
+    text.push_u64(value);
+    handle_ui_message(text.as_bytes(), message, page)
+}
+
+/// StarkEx limit order
+///
+/// Encoded as a sequence of felts:
+/// vault_id_sell, vault_id_buy, amount_sell, amount_buy,
+/// asset_id_sell, asset_id_buy, nonce, expiration_timestamp
+///
+/// Amounts are quantized and the expiration is in hours since the unix epoch
+pub struct StarkExLimitOrder<'b> {
+    pub vault_sell: u64,
+    pub vault_buy: u64,
+    pub amount_sell: u64,
+    pub amount_buy: u64,
+    pub asset_sell: &'b [u8; FELT_SIZE],
+    pub asset_buy: &'b [u8; FELT_SIZE],
+    pub nonce: u64,
+    pub expiration: u64,
+}
+
+impl<'b> StarkExLimitOrder<'b> {
+    #[inline(never)]
+    pub fn parse(data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let order = Self {
+            vault_sell: read_bits(&mut reader, VAULT_BITS)?,
+            vault_buy: read_bits(&mut reader, VAULT_BITS)?,
+            amount_sell: read_bits(&mut reader, AMOUNT_BITS)?,
+            amount_buy: read_bits(&mut reader, AMOUNT_BITS)?,
+            asset_sell: reader.felt()?,
+            asset_buy: reader.felt()?,
+            nonce: read_bits(&mut reader, NONCE_BITS)?,
+            expiration: read_bits(&mut reader, EXPIRATION_BITS)?,
+        };
+
+        reader.finish()?;
+        Ok(order)
+    }
+
+    /// Computes the message hash of the order
+    pub fn hash(&self) -> Felt {
+        message_hash(
+            Instruction::LimitOrder,
+            (self.vault_sell, self.vault_buy),
+            (self.amount_sell, self.amount_buy),
+            (self.asset_sell, self.asset_buy),
+            None,
+            self.nonce,
+            self.expiration,
+        )
+    }
+}
+
+impl<'b> DisplayableItem for StarkExLimitOrder<'b> {
+    fn num_items(&self) -> usize {
+        6
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        match item_n {
+            0 => {
+                render_title(pic_str!(b"Sell"), title);
+                render_asset_amount(self.amount_sell, self.asset_sell, message, page)
+            }
+            1 => {
+                render_title(pic_str!(b"Buy"), title);
+                render_asset_amount(self.amount_buy, self.asset_buy, message, page)
+            }
+            2 => {
+                render_title(pic_str!(b"Expiration"), title);
+                render_expiration(self.expiration, message, page)
+            }
+            3 => render_u64(
+                pic_str!(b"Sell Vault"),
+                self.vault_sell,
+                title,
+                message,
+                page,
+            ),
+            4 => render_u64(pic_str!(b"Buy Vault"), self.vault_buy, title, message, page),
+            5 => render_u64(pic_str!(b"Nonce"), self.nonce, title, message, page),
+            _ => Err(ViewError::NoData),
+        }
+    }
+}
+
+/// StarkEx transfer, optionally conditional
+///
+/// Encoded as a sequence of felts:
+/// amount, nonce, sender_vault_id, asset_id, receiver_vault_id,
+/// receiver_public_key, expiration_timestamp, [condition]
+///
+/// The amount is quantized and the expiration is in hours since the unix epoch
+pub struct StarkExTransfer<'b> {
+    pub amount: u64,
+    pub nonce: u64,
+    pub sender_vault: u64,
+    pub asset: &'b [u8; FELT_SIZE],
+    pub receiver_vault: u64,
+    pub receiver_public_key: &'b [u8; FELT_SIZE],
+    pub expiration: u64,
+    pub condition: Option<&'b [u8; FELT_SIZE]>,
+}
+
+impl<'b> StarkExTransfer<'b> {
+    #[inline(never)]
+    pub fn parse(data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let mut transfer = Self {
+            amount: read_bits(&mut reader, AMOUNT_BITS)?,
+            nonce: read_bits(&mut reader, NONCE_BITS)?,
+            sender_vault: read_bits(&mut reader, VAULT_BITS)?,
+            asset: reader.felt()?,
+            receiver_vault: read_bits(&mut reader, VAULT_BITS)?,
+            receiver_public_key: reader.felt()?,
+            expiration: read_bits(&mut reader, EXPIRATION_BITS)?,
+            condition: None,
+        };
+
+        if !reader.remaining().is_empty() {
+            transfer.condition = Some(reader.felt()?);
+        }
+
+        reader.finish()?;
+        Ok(transfer)
+    }
+
+    /// Computes the message hash of the transfer
+    pub fn hash(&self) -> Felt {
+        let instruction = if self.condition.is_some() {
+            Instruction::ConditionalTransfer
+        } else {
+            Instruction::Transfer
+        };
+
+        message_hash(
+            instruction,
+            (self.sender_vault, self.receiver_vault),
+            (self.amount, 0),
+            (self.asset, self.receiver_public_key),
+            self.condition,
+            self.nonce,
+            self.expiration,
+        )
+    }
+}
+
+impl<'b> DisplayableItem for StarkExTransfer<'b> {
+    fn num_items(&self) -> usize {
+        6 + self.condition.is_some() as usize
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        match item_n {
+            0 => {
+                render_title(pic_str!(b"Transfer"), title);
+                render_asset_amount(self.amount, self.asset, message, page)
+            }
+            1 => {
+                render_title(pic_str!(b"Receiver"), title);
+                render_felt_hex(self.receiver_public_key, message, page)
+            }
+            2 => {
+                render_title(pic_str!(b"Expiration"), title);
+                render_expiration(self.expiration, message, page)
+            }
+            3 => render_u64(
+                pic_str!(b"Sender Vault"),
+                self.sender_vault,
+                title,
+                message,
+                page,
+            ),
+            4 => render_u64(
+                pic_str!(b"Receiver Vault"),
+                self.receiver_vault,
+                title,
+                message,
+                page,
+            ),
+            5 => render_u64(pic_str!(b"Nonce"), self.nonce, title, message, page),
+            6 => {
+                let condition = self.condition.ok_or(ViewError::NoData)?;
+
+                render_title(pic_str!(b"Condition"), title);
+                render_felt_hex(condition, message, page)
+            }
+            _ => Err(ViewError::NoData),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //from the StarkEx signature test data
+    const LIMIT_ORDER: &str = "0000000000000000000000000000000000000000000000000000000000000015000000000000000000000000000000000000000000000000000000000000001b0000000000000000000000000000000000000000000000001de6fc2ed1065a7c0000000000000000000000000000000000000000000000001467596a13925b4b005fa3383597691ea9d827a79e1a4f0f7989c35ced18ca9619de8ab97e66102000774961c824a3b0fb3d2965f01471c9c7734bf8dbde659e0c08dca2ef18d56a0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000006b2a9";
+
+    const LIMIT_ORDER_HASH: &str =
+        "0397e76d1667c4454bfb83514e120583af836f8e32a516765497823eabe16a3f";
+
+    //computed with the StarkEx reference implementation
+    const TRANSFER: &str = "0000000000000000000000000000000000000000000000001de67f8a48ea9d7c0000000000000000000000000000000000000000000000000000000000000001000000000000000000000000000000000000000000000000000000000000002203003a65651d3b9fb2eff934a4416db301afd112a8492aaf8d7297fc87dcd9f40000000000000000000000000000000000000000000000000000000000000015005fa3383597691ea9d827a79e1a4f0f7989c35ced18ca9619de8ab97e661020000000000000000000000000000000000000000000000000000000000006b2a9";
+
+    const TRANSFER_HASH: &str = "020703ebcadb328f077e9a072eeb61a57a8dfca790f5c93feb1b5146a28c52f8";
+
+    const CONDITION: &str = "0318ff6d26cf3175c77668cd6434ab34d31e59f806a6a7c06d08215bccb7eaf8";
+
+    const CONDITIONAL_TRANSFER_HASH: &str =
+        "0322adb29b709df9fe6ef4cee2480ba0d8470be5cdb9f8151d324958165522f9";
+
+    #[test]
+    fn limit_order() {
+        let data = hex::decode(LIMIT_ORDER).unwrap();
+        let order = StarkExLimitOrder::parse(&data).unwrap();
+
+        assert_eq!(order.vault_sell, 21);
+        assert_eq!(order.expiration, 438953);
+        assert_eq!(hex::encode(order.hash().to_be_bytes()), LIMIT_ORDER_HASH);
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        order.render_item(0, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"Sell\0"));
+        assert!(message.starts_with(b"2154686749748910716 of 0x005fa338"));
+
+        order.render_item(2, &mut title, &mut message, 0).unwrap();
+        assert!(message.starts_with(b"2020-01-28 17:00 UTC\0"));
+    }
+
+    #[test]
+    fn transfer() {
+        let data = hex::decode(TRANSFER).unwrap();
+        let transfer = StarkExTransfer::parse(&data).unwrap();
+
+        assert_eq!(transfer.num_items(), 6);
+        assert_eq!(hex::encode(transfer.hash().to_be_bytes()), TRANSFER_HASH);
+
+        let mut data = data;
+        data.extend_from_slice(&hex::decode(CONDITION).unwrap());
+        let transfer = StarkExTransfer::parse(&data).unwrap();
+
+        assert_eq!(transfer.num_items(), 7);
+        assert_eq!(
+            hex::encode(transfer.hash().to_be_bytes()),
+            CONDITIONAL_TRANSFER_HASH
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        let mut data = hex::decode(LIMIT_ORDER).unwrap();
+
+        //vault ids are 31 bits
+        data[FELT_SIZE - 4] = 0x80;
+        let err = StarkExLimitOrder::parse(&data).err().unwrap();
+        assert_eq!(err, ParserError::ValueOutOfRange);
+    }
+}
@@ -0,0 +1,265 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Known ERC-20 tokens, to show transfers and approvals in a readable form
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::{crypto::felt::U256, handlers::handle_ui_message};
+
+use super::{felt_to_u128, felts_iter, selector_name, Call, Network, TextBuffer, FELT_SIZE};
+
+/// Metadata of an ERC-20 token contract
+#[derive(Clone, Copy)]
+pub struct Token {
+    pub network: Network,
+    pub ticker: &'static [u8],
+    pub decimals: u8,
+    pub address: [u8; FELT_SIZE],
+}
+
+/// Tokens shown in a readable form, anything else is rendered as a raw call
+///
+/// Sepolia only lists the tokens with a canonical StarkGate deployment:
+/// USDT, DAI and WBTC have no official testnet contract, and showing a
+/// community deployment with a trusted ticker would let anyone mint
+/// "USDT" that the device presents as the real thing
+#[rustfmt::skip]
+const KNOWN_TOKENS: [Token; 9] = [
+    Token {
+        network: Network::Mainnet,
+        ticker: b"ETH",
+        decimals: 18,
+        address: [
+            0x04, 0x9d, 0x36, 0x57, 0x0d, 0x4e, 0x46, 0xf4, 0x8e, 0x99, 0x67, 0x4b, 0xd3, 0xfc, 0xc8, 0x46,
+            0x44, 0xdd, 0xd6, 0xb9, 0x6f, 0x7c, 0x74, 0x1b, 0x15, 0x62, 0xb8, 0x2f, 0x9e, 0x00, 0x4d, 0xc7,
+        ],
+    },
+    Token {
+        network: Network::Mainnet,
+        ticker: b"STRK",
+        decimals: 18,
+        address: [
+            0x04, 0x71, 0x8f, 0x5a, 0x0f, 0xc3, 0x4c, 0xc1, 0xaf, 0x16, 0xa1, 0xcd, 0xee, 0x98, 0xff, 0xb2,
+            0x0c, 0x31, 0xf5, 0xcd, 0x61, 0xd6, 0xab, 0x07, 0x20, 0x18, 0x58, 0xf4, 0x28, 0x7c, 0x93, 0x8d,
+        ],
+    },
+    Token {
+        network: Network::Mainnet,
+        ticker: b"USDC",
+        decimals: 6,
+        address: [
+            0x05, 0x3c, 0x91, 0x25, 0x3b, 0xc9, 0x68, 0x2c, 0x04, 0x92, 0x9c, 0xa0, 0x2e, 0xd0, 0x0b, 0x3e,
+            0x42, 0x3f, 0x67, 0x10, 0xd2, 0xee, 0x7e, 0x0d, 0x5e, 0xbb, 0x06, 0xf3, 0xec, 0xf3, 0x68, 0xa8,
+        ],
+    },
+    Token {
+        network: Network::Mainnet,
+        ticker: b"USDT",
+        decimals: 6,
+        address: [
+            0x06, 0x8f, 0x5c, 0x6a, 0x61, 0x78, 0x07, 0x68, 0x45, 0x5d, 0xe6, 0x90, 0x77, 0xe0, 0x7e, 0x89,
+            0x78, 0x78, 0x39, 0xbf, 0x81, 0x66, 0xde, 0xcf, 0xbf, 0x92, 0xb6, 0x45, 0x20, 0x9c, 0x0f, 0xb8,
+        ],
+    },
+    Token {
+        network: Network::Mainnet,
+        ticker: b"DAI",
+        decimals: 18,
+        address: [
+            0x05, 0x57, 0x4e, 0xb6, 0xb8, 0x78, 0x9a, 0x91, 0x46, 0x6f, 0x90, 0x2c, 0x38, 0x0d, 0x97, 0x8e,
+            0x47, 0x2d, 0xb6, 0x81, 0x70, 0xff, 0x82, 0xa5, 0xb6, 0x50, 0xb9, 0x5a, 0x58, 0xdd, 0xf4, 0xad,
+        ],
+    },
+    Token {
+        network: Network::Mainnet,
+        ticker: b"WBTC",
+        decimals: 8,
+        address: [
+            0x03, 0xfe, 0x2b, 0x97, 0xc1, 0xfd, 0x33, 0x6e, 0x75, 0x00, 0x87, 0xd6, 0x8b, 0x9b, 0x86, 0x79,
+            0x97, 0xfd, 0x64, 0xa2, 0x66, 0x1f, 0xf3, 0xca, 0x5a, 0x7c, 0x77, 0x16, 0x41, 0xe8, 0xe7, 0xac,
+        ],
+    },
+    Token {
+        network: Network::Sepolia,
+        ticker: b"ETH",
+        decimals: 18,
+        address: [
+            0x04, 0x9d, 0x36, 0x57, 0x0d, 0x4e, 0x46, 0xf4, 0x8e, 0x99, 0x67, 0x4b, 0xd3, 0xfc, 0xc8, 0x46,
+            0x44, 0xdd, 0xd6, 0xb9, 0x6f, 0x7c, 0x74, 0x1b, 0x15, 0x62, 0xb8, 0x2f, 0x9e, 0x00, 0x4d, 0xc7,
+        ],
+    },
+    Token {
+        network: Network::Sepolia,
+        ticker: b"STRK",
+        decimals: 18,
+        address: [
+            0x04, 0x71, 0x8f, 0x5a, 0x0f, 0xc3, 0x4c, 0xc1, 0xaf, 0x16, 0xa1, 0xcd, 0xee, 0x98, 0xff, 0xb2,
+            0x0c, 0x31, 0xf5, 0xcd, 0x61, 0xd6, 0xab, 0x07, 0x20, 0x18, 0x58, 0xf4, 0x28, 0x7c, 0x93, 0x8d,
+        ],
+    },
+    Token {
+        network: Network::Sepolia,
+        ticker: b"USDC",
+        decimals: 6,
+        address: [
+            0x05, 0x3b, 0x40, 0xa6, 0x47, 0xce, 0xdf, 0xca, 0x6c, 0xa8, 0x4f, 0x54, 0x2a, 0x0f, 0xe3, 0x67,
+            0x36, 0x03, 0x19, 0x05, 0xa9, 0x63, 0x9a, 0x7f, 0x19, 0xa3, 0xc1, 0xe6, 0x6b, 0xfd, 0x50, 0x80,
+        ],
+    },
+];
+
+impl Token {
+    /// Returns the token deployed at `address` on `network`, if known
+    pub fn find(address: &[u8; FELT_SIZE], network: Network) -> Option<Self> {
+        let table = PIC::new(&KNOWN_TOKENS).into_inner();
+
+        table
+            .iter()
+            .find(|token| token.network == network && &token.address == address)
+            .map(|token| Self {
+                ticker: PIC::new(token.ticker).into_inner(),
+                ..*token
+            })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum TokenOperationKind {
+    /// `transfer(recipient, amount)`
+    Transfer,
+    /// `approve(spender, amount)`
+    Approve,
+}
+
+/// A call to a known token contract moving or approving funds
+pub struct TokenOperation<'b> {
+    pub kind: TokenOperationKind,
+    pub token: Token,
+    /// The recipient of a transfer or the spender of an approval
+    pub account: &'b [u8; FELT_SIZE],
+    pub amount: U256,
+}
+
+impl<'b> TokenOperation<'b> {
+    /// Recognizes `call` as a transfer or approval of a known token on `network`
+    pub fn decode(call: &Call<'b>, network: Network) -> Option<Self> {
+        let name = selector_name(call.selector)?;
+        let kind = if name == pic_str!(b"transfer") {
+            TokenOperationKind::Transfer
+        } else if name == pic_str!(b"approve") {
+            TokenOperationKind::Approve
+        } else {
+            return None;
+        };
+        let token = Token::find(call.to, network)?;
+
+        //(account, amount.low, amount.high)
+        if call.calldata_len() != 3 {
+            return None;
+        }
+        let mut args = felts_iter(call.calldata);
+        let account = args.next()?;
+        let low = felt_to_u128(args.next()?)?;
+        let high = felt_to_u128(args.next()?)?;
+
+        Some(Self {
+            kind,
+            token,
+            account,
+            amount: U256::from_low_high(low, high),
+        })
+    }
+
+    /// Renders the operation as "Send <amount> <ticker> to <recipient>"
+    /// or "Approve <amount> <ticker> for <spender>"
+    #[inline(never)]
+    pub fn render(&self, message: &mut [u8], page: u8) -> Result<u8, ViewError> {
+        let (action, preposition) = match self.kind {
+            TokenOperationKind::Transfer => (pic_str!(b"Send "), pic_str!(b" to ")),
+            TokenOperationKind::Approve => (pic_str!(b"Approve "), pic_str!(b" for ")),
+        };
+
+        let mut text = TextBuffer::<200>::new();
+        text.push(action)
+            .push_decimal(&self.amount, self.token.decimals as usize)
+            .push(pic_str!(b" "))
+            .push(self.token.ticker)
+            .push(preposition)
+            .push_address(self.account);
+
+        handle_ui_message(text.as_bytes(), message, page)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    const USDC: &str = "053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8";
+    const TRANSFER: &str = "0083afd3f4caedc6eebf44246fe54e38c95e3179a5ec9ea81740eca5b482d12e";
+    const RECIPIENT: &str = "04a2d5a3e5d5ab7ca6d4b0c37d2f1d0e9c6ca0dd1e2c8a8c4d3d4b5c6e7f8091";
+
+    fn felt(s: &str) -> [u8; FELT_SIZE] {
+        let mut bytes = [0; FELT_SIZE];
+        bytes.copy_from_slice(&hex::decode(std::format!("{:0>64}", s)).unwrap());
+        bytes
+    }
+
+    #[test]
+    fn transfer() {
+        let (to, selector) = (felt(USDC), felt(TRANSFER));
+        //1.25 USDC
+        let calldata: Vec<u8> = [felt(RECIPIENT), felt("1312d0"), felt("0")].concat();
+        let call = Call {
+            to: &to,
+            selector: &selector,
+            calldata: &calldata,
+        };
+
+        //tokens are matched by address and network, mainnet USDC isn't deployed on Sepolia
+        assert!(TokenOperation::decode(&call, Network::Sepolia).is_none());
+        let op = TokenOperation::decode(&call, Network::Mainnet).unwrap();
+        assert_eq!(op.kind, TokenOperationKind::Transfer);
+
+        let mut message = [0; 200];
+        op.render(&mut message, 0).unwrap();
+        //the recipient is checksummed
+        let expected = "Send 1.25 USDC to 0x04a2d5a3E5d5AB7Ca6d4B0C37D2F1D0E9C6CA0Dd1E2C8a8c4d3d4b5c6E7f8091\0";
+        assert_eq!(&message[..expected.len()], expected.as_bytes());
+    }
+
+    #[test]
+    fn invalid_amount() {
+        let (to, selector) = (felt(USDC), felt(TRANSFER));
+        //amount.low doesn't fit in 128 bits
+        let calldata: Vec<u8> = [
+            felt(RECIPIENT),
+            felt("100000000000000000000000000000000"),
+            felt("0"),
+        ]
+        .concat();
+        let call = Call {
+            to: &to,
+            selector: &selector,
+            calldata: &calldata,
+        };
+
+        assert!(TokenOperation::decode(&call, Network::Mainnet).is_none());
+    }
+}
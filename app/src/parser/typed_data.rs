@@ -0,0 +1,431 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! SNIP-12 revision 1 typed data, hashed with Poseidon
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::{
+    crypto::{
+        felt::Felt,
+        keccak::starknet_keccak,
+        poseidon::{poseidon_hash_many, PoseidonHasher},
+    },
+    handlers::handle_ui_message,
+    utils::{hex_encode, ApduPanic},
+};
+
+use super::{
+    felt_to_u128, felt_to_u64, felts_iter, render_address, render_felt_decimal, render_felt_hex,
+    render_network, render_short_string, render_title, to_felt, DisplayableItem, FeltReader,
+    ParserError, FELT_SIZE,
+};
+
+/// "StarkNet Message" as a short string
+const MESSAGE_PREFIX: u128 = 0x537461726b4e6574204d657373616765;
+
+const REVISION: u64 = 1;
+
+/// Maximum number of fields of the message
+const MAX_FIELDS: usize = 64;
+
+/// Iterates over the fields of the first type of an encoded type string,
+/// yielding (name, type) pairs, or `None` when the encoding is malformed
+///
+/// The encoding looks like `"Mail"("from":"ContractAddress","contents":"shortstring")`,
+/// optionally followed by the encoding of the referenced types
+struct TypeFields<'b> {
+    data: &'b [u8],
+    done: bool,
+}
+
+impl<'b> TypeFields<'b> {
+    /// Returns the name of the type and an iterator over its fields
+    fn parse(data: &'b [u8]) -> Option<(&'b [u8], Self)> {
+        let mut fields = Self { data, done: false };
+        let name = fields.quoted()?;
+        fields.expect(b'(')?;
+
+        if fields.data.first() == Some(&b')') {
+            fields.done = true;
+        }
+
+        Some((name, fields))
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        let (&first, rest) = self.data.split_first()?;
+        if first != c {
+            return None;
+        }
+
+        self.data = rest;
+        Some(())
+    }
+
+    fn quoted(&mut self) -> Option<&'b [u8]> {
+        self.expect(b'"')?;
+        let len = self.data.iter().position(|&c| c == b'"')?;
+        let (content, rest) = self.data.split_at(len);
+
+        self.data = &rest[1..];
+        Some(content)
+    }
+
+    fn field(&mut self) -> Option<(&'b [u8], &'b [u8])> {
+        let name = self.quoted()?;
+        self.expect(b':')?;
+        let ty = self.quoted()?;
+
+        match self.data.first()? {
+            b',' => self.expect(b',')?,
+            b')' => self.done = true,
+            _ => return None,
+        }
+
+        Some((name, ty))
+    }
+}
+
+impl<'b> Iterator for TypeFields<'b> {
+    type Item = Option<(&'b [u8], &'b [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let field = self.field();
+        if field.is_none() {
+            self.done = true;
+        }
+
+        Some(field)
+    }
+}
+
+/// A message to sign, following SNIP-12 revision 1
+///
+/// Encoded as a sequence of felts:
+/// domain.name, domain.version, domain.chainId, domain.revision,
+/// account, values_len, values...,
+/// followed by the encoded type of the message (as in `encodeType`) as raw bytes
+///
+/// The values are the encoding of each field of the message,
+/// as they are fed to the hash of the struct
+pub struct TypedData<'b> {
+    pub name: &'b [u8; FELT_SIZE],
+    pub version: &'b [u8; FELT_SIZE],
+    pub chain_id: &'b [u8; FELT_SIZE],
+    pub account: &'b [u8; FELT_SIZE],
+    pub values: &'b [u8],
+    pub encoded_type: &'b [u8],
+}
+
+impl<'b> TypedData<'b> {
+    /// SNIP-12 revision supported
+    pub const REVISION: u64 = REVISION;
+
+    #[inline(never)]
+    pub fn parse(data: &'b [u8]) -> Result<Self, ParserError> {
+        let mut reader = FeltReader::new(data);
+
+        let name = reader.felt()?;
+        let version = reader.felt()?;
+        let chain_id = reader.felt()?;
+        if reader.u64()? != REVISION {
+            return Err(ParserError::ValueOutOfRange);
+        }
+        let account = reader.felt()?;
+        let values = reader.array()?;
+        let encoded_type = reader.remaining();
+
+        let (_, fields) = TypeFields::parse(encoded_type).ok_or(ParserError::UnexpectedData)?;
+        let mut field_values = felts_iter(values);
+        let mut n_fields = 0;
+        for field in fields {
+            let (_, ty) = field.ok_or(ParserError::UnexpectedData)?;
+
+            //shown as a decimal number, which must be the value that was hashed
+            if let Some(value) = field_values.next() {
+                if is_u128(ty) && felt_to_u128(value).is_none() {
+                    return Err(ParserError::ValueOutOfRange);
+                }
+            }
+            n_fields += 1;
+        }
+        if n_fields != values.len() / FELT_SIZE || n_fields > MAX_FIELDS {
+            return Err(ParserError::UnexpectedData);
+        }
+
+        Ok(Self {
+            name,
+            version,
+            chain_id,
+            account,
+            values,
+            encoded_type,
+        })
+    }
+
+    /// Computes the hash of the message
+    #[inline(never)]
+    pub fn hash(&self) -> Felt {
+        let domain_type = pic_str!(
+            b"\"StarknetDomain\"(\"name\":\"shortstring\",\"version\":\"shortstring\",\"chainId\":\"shortstring\",\"revision\":\"shortstring\")"
+        );
+        let domain_hash = poseidon_hash_many([
+            starknet_keccak(domain_type),
+            to_felt(self.name),
+            to_felt(self.version),
+            to_felt(self.chain_id),
+            Felt::from_u64(REVISION),
+        ]);
+
+        let mut hasher = PoseidonHasher::default();
+        hasher.update(&starknet_keccak(self.encoded_type));
+        for value in felts_iter(self.values) {
+            hasher.update(&to_felt(value));
+        }
+        let struct_hash = hasher.finalize();
+
+        poseidon_hash_many([
+            Felt::from_u128(MESSAGE_PREFIX),
+            domain_hash,
+            to_felt(self.account),
+            struct_hash,
+        ])
+    }
+
+    fn n_fields(&self) -> usize {
+        self.values.len() / FELT_SIZE
+    }
+
+    /// Returns whether any field of the message has a type the device can't show,
+    /// signing such a message is blind signing
+    pub fn has_unverified_fields(&self) -> bool {
+        TypeFields::parse(self.encoded_type)
+            .map(|(_, fields)| fields.flatten().any(|(_, ty)| !is_verified(ty)))
+            .unwrap_or(true)
+    }
+}
+
+/// Returns whether the device can show values of type `ty` as the user expects them
+///
+/// Other types (felt, u256, string, selector, enums, arrays, nested structs...)
+/// are encoded or already hashed by the host, so their value is shown as hex
+/// and can't be checked against what the dapp displayed
+fn is_verified(ty: &[u8]) -> bool {
+    ty == pic_str!(b"shortstring")
+        || ty == pic_str!(b"ContractAddress")
+        || ty == pic_str!(b"u128")
+        || ty == pic_str!(b"timestamp")
+        || ty == pic_str!(b"bool")
+}
+
+/// Returns whether values of type `ty` are integers of at most 128 bits
+fn is_u128(ty: &[u8]) -> bool {
+    ty == pic_str!(b"u128") || ty == pic_str!(b"timestamp")
+}
+
+/// Renders `value` according to the type of the field
+fn render_value(
+    ty: &[u8],
+    value: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    if ty == pic_str!(b"shortstring") {
+        render_short_string(value, message, page)
+    } else if ty == pic_str!(b"ContractAddress") {
+        render_address(value, message, page)
+    } else if is_u128(ty) {
+        render_felt_decimal(value, message, page)
+    } else if ty == pic_str!(b"bool") && felt_to_u64(value) == Some(0) {
+        handle_ui_message(pic_str!(b"false"), message, page)
+    } else if ty == pic_str!(b"bool") && felt_to_u64(value) == Some(1) {
+        handle_ui_message(pic_str!(b"true"), message, page)
+    } else if is_verified(ty) {
+        render_felt_hex(value, message, page)
+    } else {
+        render_unverified(value, message, page)
+    }
+}
+
+/// Renders the felt as hex, labelled as a value the user can't verify
+fn render_unverified(
+    value: &[u8; FELT_SIZE],
+    message: &mut [u8],
+    page: u8,
+) -> Result<u8, ViewError> {
+    let label = pic_str!(b"Unverified ");
+    let mut buf = [0; 11 + 2 + FELT_SIZE * 2];
+    buf[..label.len()].copy_from_slice(label);
+    buf[label.len()..label.len() + 2].copy_from_slice(b"0x");
+    let len = hex_encode(&value[..], &mut buf[label.len() + 2..]).apdu_unwrap();
+
+    handle_ui_message(&buf[..label.len() + 2 + len], message, page)
+}
+
+impl<'b> DisplayableItem for TypedData<'b> {
+    fn num_items(&self) -> usize {
+        4 + self.n_fields()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        let (type_name, mut fields) =
+            TypeFields::parse(self.encoded_type).ok_or(ViewError::Unknown)?;
+
+        match item_n {
+            0 => {
+                render_title(pic_str!(b"Domain"), title);
+                render_short_string(self.name, message, page)
+            }
+            1 => {
+                render_title(pic_str!(b"Version"), title);
+                render_short_string(self.version, message, page)
+            }
+            2 => render_network(self.chain_id, title, message, page),
+            3 => {
+                render_title(pic_str!(b"Message"), title);
+                handle_ui_message(type_name, message, page)
+            }
+            n => {
+                let idx = (n - 4) as usize;
+                let (name, ty) = fields.nth(idx).flatten().ok_or(ViewError::NoData)?;
+                let value = felts_iter(self.values).nth(idx).ok_or(ViewError::NoData)?;
+
+                render_title(name, title);
+                render_value(ty, value, message, page)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //"Mail"("from":"ContractAddress","to":"ContractAddress","contents":"shortstring","sent":"timestamp")
+    const SERIALIZED: &str = "000000000000000000000000000000000000000000000000004578616d706c65000000000000000000000000000000000000000000000000000000000000003100000000000000000000000000000000000000000000000000534e5f4d41494e00000000000000000000000000000000000000000000000000000000000000010000000000000000000000000000000000000000000000000000000000001234000000000000000000000000000000000000000000000000000000000000000400000000000000000000000000000000000000000000000000000000000000aa00000000000000000000000000000000000000000000000000000000000000bb00000000000000000000000000000000000000000000000000000068656c6c6f000000000000000000000000000000000000000000000000000000006553f100224d61696c22282266726f6d223a22436f6e747261637441646472657373222c22746f223a22436f6e747261637441646472657373222c22636f6e74656e7473223a2273686f7274737472696e67222c2273656e74223a2274696d657374616d702229";
+
+    const EXPECTED_HASH: &str = "034e46712f767f5af7ec5ce0e0f514114cc014f9d33d9ae570314fbbc835db72";
+
+    #[test]
+    fn type_fields() {
+        let (name, fields) =
+            TypeFields::parse(b"\"Pair\"(\"a\":\"felt\",\"b\":\"u128*\")").unwrap();
+        assert_eq!(name, b"Pair");
+
+        let fields = fields.collect::<Option<std::vec::Vec<_>>>().unwrap();
+        assert_eq!(
+            fields,
+            [(&b"a"[..], &b"felt"[..]), (&b"b"[..], &b"u128*"[..])]
+        );
+
+        let (_, mut fields) = TypeFields::parse(b"\"Empty\"()").unwrap();
+        assert!(fields.next().is_none());
+
+        let (_, mut fields) = TypeFields::parse(b"\"Bad\"(\"a\":\"felt\"").unwrap();
+        assert_eq!(fields.next(), Some(None));
+        assert!(TypeFields::parse(b"Bad(a:felt)").is_none());
+    }
+
+    #[test]
+    fn parse_and_hash() {
+        let data = hex::decode(SERIALIZED).unwrap();
+        let msg = TypedData::parse(&data).unwrap();
+
+        assert_eq!(msg.num_items(), 4 + 4);
+        assert!(!msg.has_unverified_fields());
+        assert_eq!(hex::encode(msg.hash().to_be_bytes()), EXPECTED_HASH);
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        msg.render_item(0, &mut title, &mut message, 0).unwrap();
+        assert!(message.starts_with(b"Example\0"));
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        msg.render_item(1, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"Version\0"));
+        assert!(message.starts_with(b"1\0"));
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        msg.render_item(6, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"contents\0"));
+        assert!(message.starts_with(b"hello\0"));
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        msg.render_item(7, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"sent\0"));
+        assert!(message.starts_with(b"1700000000\0"));
+    }
+
+    #[test]
+    fn unverified_fields() {
+        //"Pair"("a":"shortstring","b":"felt")
+        let mut data = hex::decode(&SERIALIZED[..5 * 64]).unwrap();
+        data.extend_from_slice(&[0; FELT_SIZE - 1]);
+        data.push(2);
+        data.extend_from_slice(&[0; FELT_SIZE - 2]);
+        data.extend_from_slice(b"hi");
+        data.extend_from_slice(&[0; FELT_SIZE - 1]);
+        data.push(0xab);
+        data.extend_from_slice(b"\"Pair\"(\"a\":\"shortstring\",\"b\":\"felt\")");
+
+        let msg = TypedData::parse(&data).unwrap();
+        assert!(msg.has_unverified_fields());
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        msg.render_item(4, &mut title, &mut message, 0).unwrap();
+        assert!(message.starts_with(b"hi\0"));
+
+        let (mut title, mut message) = ([0; 40], [0; 200]);
+        msg.render_item(5, &mut title, &mut message, 0).unwrap();
+        assert!(title.starts_with(b"b\0"));
+        let expected = std::format!("Unverified 0x{}ab\0", "0".repeat(62));
+        assert_eq!(&message[..expected.len()], expected.as_bytes());
+    }
+
+    #[test]
+    fn malformed() {
+        let data = hex::decode(SERIALIZED).unwrap();
+
+        //unsupported revision
+        let mut invalid = data.clone();
+        invalid[4 * FELT_SIZE - 1] = 0;
+        let err = TypedData::parse(&invalid).err().unwrap();
+        assert_eq!(err, ParserError::ValueOutOfRange);
+
+        //"sent" is a timestamp, which doesn't fit in 128 bits
+        let mut invalid = data.clone();
+        invalid[9 * FELT_SIZE + 15] = 1;
+        let err = TypedData::parse(&invalid).err().unwrap();
+        assert_eq!(err, ParserError::ValueOutOfRange);
+
+        //one value less than the fields of the type
+        let mut invalid = data;
+        invalid[6 * FELT_SIZE - 1] = 3;
+        let err = TypedData::parse(&invalid).err().unwrap();
+        assert_eq!(err, ParserError::UnexpectedData);
+    }
+}
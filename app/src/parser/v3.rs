@@ -0,0 +1,263 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Fields shared by all the v3 transactions
+
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::crypto::{
+    felt::{Felt, U256},
+    poseidon::{poseidon_hash_many, PoseidonHasher},
+};
+
+use super::{
+    felts_iter, render_amount, render_felt, render_felt_decimal, render_network, render_title,
+    to_felt, DisplayableItem, FeltReader, ParserError, FELT_SIZE,
+};
+
+/// "L1_GAS" as a short string
+const L1_GAS: u64 = 0x4c315f474153;
+
+/// "L2_GAS" as a short string
+const L2_GAS: u64 = 0x4c325f474153;
+
+/// "L1_DATA" as a short string
+const L1_DATA: u64 = 0x4c315f44415441;
+
+/// Decimals of the fee token (STRK) of v3 transactions
+const FEE_DECIMALS: usize = 18;
+
+/// Limits on the usage of a resource by the transaction
+#[derive(Clone, Copy)]
+pub struct ResourceBounds {
+    pub max_amount: u64,
+    pub max_price_per_unit: u128,
+}
+
+impl ResourceBounds {
+    fn parse(reader: &mut FeltReader<'_>) -> Result<Self, ParserError> {
+        Ok(Self {
+            max_amount: reader.u64()?,
+            max_price_per_unit: reader.u128()?,
+        })
+    }
+
+    /// Encodes the bounds of the resource `name` as used in the transaction hash:
+    /// name (bits 192..) | max_amount (bits 128..192) | max_price_per_unit (bits 0..128)
+    fn to_felt(self, name: u64) -> Felt {
+        let price = self.max_price_per_unit;
+        let packed = U256([price as u64, (price >> 64) as u64, self.max_amount, name]);
+
+        //the name is a short string smaller than 2^59, so this is always a valid felt
+        Felt::from_u256_reduced(packed)
+    }
+
+    /// Returns the maximum fee that can be charged for the resource
+    pub fn max_fee(&self) -> U256 {
+        //a 128 bit number times a 64 bit number always fits
+        let (fee, _) =
+            U256::from_u128(self.max_price_per_unit).overflowing_mul_small(self.max_amount);
+        fee
+    }
+}
+
+/// Where the data of the transaction (nonce or fee) is to be published
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+pub enum DataAvailabilityMode {
+    L1 = 0,
+    L2 = 1,
+}
+
+impl DataAvailabilityMode {
+    fn parse(reader: &mut FeltReader<'_>) -> Result<Self, ParserError> {
+        match reader.u64()? {
+            0 => Ok(Self::L1),
+            1 => Ok(Self::L2),
+            _ => Err(ParserError::ValueOutOfRange),
+        }
+    }
+}
+
+/// Fields common to all v3 transactions
+///
+/// Encoded as a sequence of felts:
+/// nonce, chain_id, tip,
+/// l1_gas.max_amount, l1_gas.max_price_per_unit,
+/// l2_gas.max_amount, l2_gas.max_price_per_unit,
+/// [l1_data_gas.max_amount, l1_data_gas.max_price_per_unit,]
+/// nonce_data_availability_mode, fee_data_availability_mode,
+/// paymaster_data_len, paymaster_data...
+pub struct CommonFieldsV3<'b> {
+    pub nonce: &'b [u8; FELT_SIZE],
+    pub chain_id: &'b [u8; FELT_SIZE],
+    pub tip: u64,
+    pub l1_gas: ResourceBounds,
+    pub l2_gas: ResourceBounds,
+    /// Only sent by hosts following Starknet 0.13.4 and later
+    pub l1_data_gas: Option<ResourceBounds>,
+    pub nonce_da_mode: DataAvailabilityMode,
+    pub fee_da_mode: DataAvailabilityMode,
+    pub paymaster_data: &'b [u8],
+}
+
+impl<'b> CommonFieldsV3<'b> {
+    pub const VERSION: u64 = 3;
+
+    /// Parses the common fields, with L1 data gas bounds if `with_l1_data_gas`
+    #[inline(never)]
+    pub fn parse(reader: &mut FeltReader<'b>, with_l1_data_gas: bool) -> Result<Self, ParserError> {
+        Ok(Self {
+            nonce: reader.felt()?,
+            chain_id: reader.felt()?,
+            tip: reader.u64()?,
+            l1_gas: ResourceBounds::parse(reader)?,
+            l2_gas: ResourceBounds::parse(reader)?,
+            l1_data_gas: if with_l1_data_gas {
+                Some(ResourceBounds::parse(reader)?)
+            } else {
+                None
+            },
+            nonce_da_mode: DataAvailabilityMode::parse(reader)?,
+            fee_da_mode: DataAvailabilityMode::parse(reader)?,
+            paymaster_data: reader.array()?,
+        })
+    }
+
+    pub fn paymaster_data_len(&self) -> usize {
+        self.paymaster_data.len() / FELT_SIZE
+    }
+
+    fn n_bounds(&self) -> usize {
+        2 + self.l1_data_gas.is_some() as usize
+    }
+
+    /// Feeds the common fields to `hasher`, in the order used by all v3 transactions:
+    ///
+    /// fee_hash, paymaster_data_hash, chain_id, nonce, data_availability_modes
+    #[inline(never)]
+    pub fn hash_into(&self, hasher: &mut PoseidonHasher) {
+        let fee_hash = match self.l1_data_gas {
+            Some(l1_data_gas) => poseidon_hash_many([
+                Felt::from_u64(self.tip),
+                self.l1_gas.to_felt(L1_GAS),
+                self.l2_gas.to_felt(L2_GAS),
+                l1_data_gas.to_felt(L1_DATA),
+            ]),
+            None => poseidon_hash_many([
+                Felt::from_u64(self.tip),
+                self.l1_gas.to_felt(L1_GAS),
+                self.l2_gas.to_felt(L2_GAS),
+            ]),
+        };
+        let paymaster_hash = poseidon_hash_many(felts_iter(self.paymaster_data).map(to_felt));
+        let da_modes = ((self.nonce_da_mode as u64) << 32) + self.fee_da_mode as u64;
+
+        hasher.update(&fee_hash);
+        hasher.update(&paymaster_hash);
+        hasher.update(&to_felt(self.chain_id));
+        hasher.update(&to_felt(self.nonce));
+        hasher.update(&Felt::from_u64(da_modes));
+    }
+}
+
+impl<'b> DisplayableItem for CommonFieldsV3<'b> {
+    fn num_items(&self) -> usize {
+        self.n_bounds() + 3 + self.paymaster_data_len()
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        let n_bounds = self.n_bounds() as u8;
+        if item_n < n_bounds {
+            let bounds = match item_n {
+                0 => {
+                    render_title(pic_str!(b"Max Fee L1 Gas"), title);
+                    self.l1_gas
+                }
+                1 => {
+                    render_title(pic_str!(b"Max Fee L2 Gas"), title);
+                    self.l2_gas
+                }
+                _ => {
+                    render_title(pic_str!(b"Max Fee L1 Data"), title);
+                    self.l1_data_gas.ok_or(ViewError::NoData)?
+                }
+            };
+
+            return render_amount(
+                &bounds.max_fee(),
+                FEE_DECIMALS,
+                pic_str!(b"STRK"),
+                message,
+                page,
+            );
+        }
+
+        match item_n - n_bounds {
+            0 => {
+                render_title(pic_str!(b"Tip"), title);
+                render_amount(&U256::from_u64(self.tip), 0, &[], message, page)
+            }
+            1 => {
+                render_title(pic_str!(b"Nonce"), title);
+                render_felt_decimal(self.nonce, message, page)
+            }
+            2 => render_network(self.chain_id, title, message, page),
+            n => {
+                let idx = (n - 3) as usize;
+                let felt = felts_iter(self.paymaster_data)
+                    .nth(idx)
+                    .ok_or(ViewError::NoData)?;
+
+                render_title(pic_str!(b"Paymaster Data"), title);
+                render_felt(felt, message, page)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_bounds() {
+        let bounds = ResourceBounds {
+            max_amount: 100_000,
+            max_price_per_unit: 100_000_000_000_000,
+        };
+
+        assert_eq!(
+            hex::encode(bounds.to_felt(L1_GAS).to_be_bytes()),
+            "00004c315f474153\
+             00000000000186a0\
+             000000000000000000005af3107a4000"
+        );
+
+        //10 STRK
+        assert_eq!(
+            bounds.max_fee(),
+            U256::from_u128(10_000_000_000_000_000_000)
+        );
+    }
+}
@@ -0,0 +1,315 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Policy for the derivation paths accepted by the app
+//!
+//! Paths follow EIP-2645:
+//! `m/2645'/layer'/application'/eth_address_1'/eth_address_2'/index`
+use bolos::{pic_str, PIC};
+use zemu_sys::ViewError;
+
+use crate::{
+    constants::{ApduError, STARKNET_BIP32_PATH_1, STARK_BIP32_PATH_0, STARK_BIP32_PATH_1},
+    handlers::handle_ui_message,
+    parser::{render_title, DisplayableItem, TextBuffer},
+};
+
+const HARDENED: u32 = 0x8000_0000;
+
+/// Number of components of an EIP-2645 path
+pub const EIP2645_DEPTH: usize = 6;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "derive-debug"), derive(Debug))]
+pub enum PathError {
+    /// The path doesn't start with the EIP-2645 purpose
+    InvalidPurpose,
+    /// The path has more components than EIP-2645 defines
+    TooDeep,
+}
+
+impl From<PathError> for ApduError {
+    fn from(_: PathError) -> Self {
+        Self::DataInvalid
+    }
+}
+
+/// The layers of a standard EIP-2645 path, without the hardening bit
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "derive-debug"), derive(Debug))]
+pub struct Eip2645Path {
+    pub layer: u32,
+    pub application: u32,
+    pub eth_address_1: u32,
+    pub eth_address_2: u32,
+    pub index: u32,
+}
+
+impl Eip2645Path {
+    /// Parses a path following the standard
+    ///
+    /// All layers but the index must be hardened, the index must not be,
+    /// and the layer must be one of the known Stark layers
+    pub fn parse(components: &[u32]) -> Option<Self> {
+        if components.len() != EIP2645_DEPTH || components[0] != STARK_BIP32_PATH_0 {
+            return None;
+        }
+
+        let layer = components[1];
+        if layer != STARK_BIP32_PATH_1 && layer != STARKNET_BIP32_PATH_1 {
+            return None;
+        }
+
+        if components[2..5].iter().any(|&c| c & HARDENED == 0) || components[5] & HARDENED != 0 {
+            return None;
+        }
+
+        Some(Self {
+            layer: layer & !HARDENED,
+            application: components[2] & !HARDENED,
+            eth_address_1: components[3] & !HARDENED,
+            eth_address_2: components[4] & !HARDENED,
+            index: components[5],
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "derive-debug"), derive(Debug))]
+pub enum PathKind {
+    Standard(Eip2645Path),
+    /// The path is accepted but needs to be shown to the user
+    Unusual,
+}
+
+/// Checks the path against the policy of the app
+///
+/// Paths outside of the EIP-2645 purpose or deeper than the standard are refused,
+/// any other deviation makes the path [`PathKind::Unusual`]
+pub fn check_path(components: &[u32]) -> Result<PathKind, PathError> {
+    if components.first() != Some(&STARK_BIP32_PATH_0) {
+        return Err(PathError::InvalidPurpose);
+    }
+
+    if components.len() > EIP2645_DEPTH {
+        return Err(PathError::TooDeep);
+    }
+
+    Ok(Eip2645Path::parse(components)
+        .map(PathKind::Standard)
+        .unwrap_or(PathKind::Unusual))
+}
+
+/// Returns true if the path should be reviewed by the user
+pub fn is_unusual(components: &[u32]) -> bool {
+    !matches!(check_path(components), Ok(PathKind::Standard(_)))
+}
+
+/// Renders the path as `m/2645'/579218131'/...`
+#[inline(never)]
+pub fn render_path(components: &[u32], message: &mut [u8], page: u8) -> Result<u8, ViewError> {
+    //"m" and up to 10 components of 10 digits, an apostrophe and a slash
+    let mut text = TextBuffer::<{ 1 + 10 * 12 }>::new();
+    text.push(pic_str!(b"m"));
+    for &c in components {
+        text.push(pic_str!(b"/")).push_u64((c & !HARDENED) as u64);
+        if c & HARDENED != 0 {
+            text.push(pic_str!(b"'"));
+        }
+    }
+
+    handle_ui_message(text.as_bytes(), message, page)
+}
+
+/// Items showing the derivation path, shown before the rest of the UI
+///
+/// Unusual paths are preceded by a warning
+pub struct PathItems<'p> {
+    components: &'p [u32],
+}
+
+impl<'p> PathItems<'p> {
+    pub fn new(components: &'p [u32]) -> Self {
+        Self { components }
+    }
+}
+
+impl<'p> DisplayableItem for PathItems<'p> {
+    fn num_items(&self) -> usize {
+        1 + is_unusual(self.components) as usize
+    }
+
+    #[inline(never)]
+    fn render_item(
+        &self,
+        item_n: u8,
+        title: &mut [u8],
+        message: &mut [u8],
+        page: u8,
+    ) -> Result<u8, ViewError> {
+        //the warning only exists for unusual paths
+        let item_n = (item_n as usize + 2)
+            .checked_sub(self.num_items())
+            .ok_or(ViewError::NoData)?;
+
+        match item_n {
+            0 => {
+                render_title(pic_str!(b"Warning"), title);
+                handle_ui_message(pic_str!(b"Unusual path"), message, page)
+            }
+            1 => {
+                render_title(pic_str!(b"Path"), title);
+                render_path(self.components, message, page)
+            }
+            _ => Err(ViewError::NoData),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: u32 = HARDENED;
+
+    fn render(components: &[u32]) -> std::string::String {
+        let mut message = [0u8; 128];
+        render_path(components, &mut message, 0).unwrap();
+        let len = message.iter().position(|&c| c == 0).unwrap();
+
+        std::string::String::from_utf8(message[..len].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn standard_paths() {
+        let path = [2645 | H, 1195502025 | H, 1148870696 | H, H, H, 3];
+        assert_eq!(
+            check_path(&path),
+            Ok(PathKind::Standard(Eip2645Path {
+                layer: 1195502025,
+                application: 1148870696,
+                eth_address_1: 0,
+                eth_address_2: 0,
+                index: 3,
+            }))
+        );
+
+        let path = [2645 | H, 579218131 | H, 7 | H, 1 | H, 2 | H, 0];
+        assert!(matches!(check_path(&path), Ok(PathKind::Standard(_))));
+        assert!(!is_unusual(&path));
+    }
+
+    #[test]
+    fn unusual_paths() {
+        //too short
+        assert_eq!(
+            check_path(&[2645 | H, 579218131 | H, H, H]),
+            Ok(PathKind::Unusual)
+        );
+        //hardened index
+        assert_eq!(
+            check_path(&[2645 | H, 579218131 | H, H, H, H, H]),
+            Ok(PathKind::Unusual)
+        );
+        //unhardened application
+        assert_eq!(
+            check_path(&[2645 | H, 579218131 | H, 0, H, H, 0]),
+            Ok(PathKind::Unusual)
+        );
+        //unknown layer
+        assert_eq!(
+            check_path(&[2645 | H, 1 | H, H, H, H, 0]),
+            Ok(PathKind::Unusual)
+        );
+        assert!(is_unusual(&[2645 | H]));
+    }
+
+    #[test]
+    fn refused_paths() {
+        assert_eq!(check_path(&[]), Err(PathError::InvalidPurpose));
+        assert_eq!(
+            check_path(&[44 | H, 60 | H]),
+            Err(PathError::InvalidPurpose)
+        );
+        assert_eq!(
+            check_path(&[2645, 579218131 | H]),
+            Err(PathError::InvalidPurpose)
+        );
+        assert_eq!(
+            check_path(&[2645 | H, 579218131 | H, H, H, H, 0, 0]),
+            Err(PathError::TooDeep)
+        );
+    }
+
+    #[test]
+    fn renders_path() {
+        assert_eq!(
+            render(&[2645 | H, 579218131 | H, H, H]),
+            "m/2645'/579218131'/0'/0'"
+        );
+        assert_eq!(
+            render(&[2645 | H, 1195502025 | H, 1148870696 | H, H, H, 0x7FFF_FFFF]),
+            "m/2645'/1195502025'/1148870696'/0'/0'/2147483647"
+        );
+    }
+
+    #[test]
+    fn pages_long_paths() {
+        let path = [2645 | H, 1195502025 | H, 1148870696 | H, H, H, 0];
+        let expected = "m/2645'/1195502025'/1148870696'/0'/0'/0";
+
+        let mut message = [0u8; 17];
+        let pages = render_path(&path, &mut message, 0).unwrap();
+        assert_eq!(pages, 3);
+
+        let mut rendered = std::vec::Vec::new();
+        for page in 0..pages {
+            render_path(&path, &mut message, page).unwrap();
+            let len = message.iter().position(|&c| c == 0).unwrap();
+            rendered.extend_from_slice(&message[..len]);
+        }
+        assert_eq!(rendered, expected.as_bytes());
+    }
+
+    #[test]
+    fn path_items() {
+        let mut title = [0u8; 16];
+        let mut message = [0u8; 64];
+
+        let standard = PathItems::new(&[2645 | H, 579218131 | H, H, H, H, 0]);
+        assert_eq!(standard.num_items(), 1);
+        standard
+            .render_item(0, &mut title, &mut message, 0)
+            .unwrap();
+        assert!(title.starts_with(b"Path"));
+        assert!(standard
+            .render_item(1, &mut title, &mut message, 0)
+            .is_err());
+
+        let unusual = PathItems::new(&[2645 | H, 579218131 | H, H, H]);
+        assert_eq!(unusual.num_items(), 2);
+        unusual.render_item(0, &mut title, &mut message, 0).unwrap();
+        assert!(message.starts_with(b"Unusual path\0"));
+        unusual.render_item(1, &mut title, &mut message, 0).unwrap();
+        assert!(message.starts_with(b"m/2645'/579218131'/0'/0'\0"));
+
+        for items in [standard, unusual].iter() {
+            assert!(matches!(
+                items.render_item(u8::MAX, &mut title, &mut message, 0),
+                Err(ViewError::NoData)
+            ));
+        }
+    }
+}
@@ -22,12 +22,29 @@ git_testament::git_testament_macros!(git);
 
 pub const GIT_COMMIT_HASH: &str = git_commit_hash!();
 
+/// Description of the commit, mentioning if it was "dirty"
+pub const GIT_TESTAMENT: &str = git_testament!();
+
+/// Returns if the app was built with uncommitted changes
+pub fn git_dirty() -> bool {
+    PIC::new(GIT_TESTAMENT.as_bytes())
+        .into_inner()
+        .windows(6)
+        .any(|w| w == b" dirty")
+}
+
 mod apdu_wrapper;
 pub use apdu_wrapper::*;
 
 mod buffer_upload;
 pub use buffer_upload::*;
 
+mod decimal;
+pub use decimal::*;
+
+mod response;
+pub use response::*;
+
 #[cfg(test)]
 #[macro_export]
 macro_rules! assert_error_code {
@@ -76,19 +76,28 @@ pub struct UploaderOutput {
     pub first: &'static [u8],
     pub data: &'static [u8],
     accessor: BUFFERAccessors,
+    persist: bool,
+}
+
+impl UploaderOutput {
+    /// Keep `BUFFER` locked and pinned when the output is dropped
+    ///
+    /// This is necessary when `first` or `data` are referenced past the current APDU,
+    /// for example by an UI, which is then responsible to call [`Uploader::release`].
+    /// Until then, any upload or response trying to use `BUFFER` fails with `Busy`,
+    /// so what `first` and `data` point to can't change
+    pub fn persist(&mut self) -> Result<(), UploaderError> {
+        unsafe { BUFFER.pin(self.accessor)? };
+        self.persist = true;
+
+        Ok(())
+    }
 }
 
 impl Drop for UploaderOutput {
     fn drop(&mut self) {
-        unsafe {
-            if let Ok(zbuffer) = BUFFER.acquire(self.accessor) {
-                zbuffer.reset();
-
-                //we managed to acquire so we should release too
-                let _ = BUFFER.release(self.accessor);
-            }
-
-            //couldn't acquire BUFFER so someone is trying to use it
+        if !self.persist {
+            Uploader::release(self.accessor);
         }
     }
 }
@@ -100,6 +109,23 @@ impl Uploader {
         }
     }
 
+    /// Reset and release `BUFFER` if it's locked by `accessor`
+    pub fn release(accessor: impl Into<BUFFERAccessors>) {
+        let accessor = accessor.into();
+
+        unsafe {
+            let _ = BUFFER.unpin(accessor);
+            if let Ok(zbuffer) = BUFFER.acquire(accessor) {
+                zbuffer.reset();
+
+                //we managed to acquire so we should release too
+                let _ = BUFFER.release(accessor);
+            }
+
+            //couldn't acquire BUFFER so someone is trying to use it
+        }
+    }
+
     #[inline(never)]
     pub fn upload(
         &mut self,
@@ -144,6 +170,7 @@ impl Uploader {
                 first: head,
                 data: tail,
                 accessor: self.accessor,
+                persist: false,
             }))
         } else {
             Err(UploaderError::PacketTypeInvalid)
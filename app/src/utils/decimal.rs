@@ -0,0 +1,114 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+use crate::crypto::felt::U256;
+
+use super::OutputBufferTooSmall;
+
+/// Number of decimal digits of 2^256
+const MAX_DIGITS: usize = 78;
+
+/// Writes `value` in base 10, with the last `decimals` digits as the fractional part
+///
+/// Trailing zeros of the fractional part are omitted,
+/// as is the decimal point when there's no fractional part left
+pub fn u256_to_decimal(
+    value: &U256,
+    decimals: usize,
+    output: &mut [u8],
+) -> Result<usize, OutputBufferTooSmall> {
+    //digits are stored starting from the least significant
+    let mut digits = [b'0'; MAX_DIGITS];
+    let mut n = *value;
+    let mut len = 0;
+    while !n.is_zero() && len < MAX_DIGITS {
+        digits[len] = b'0' + n.div_rem_small(10) as u8;
+        len += 1;
+    }
+
+    let digit_at = |i: usize| digits.get(i).copied().unwrap_or(b'0');
+
+    let int_len = core::cmp::max(len.saturating_sub(decimals), 1);
+
+    let mut trailing_zeros = 0;
+    while trailing_zeros < decimals && digit_at(trailing_zeros) == b'0' {
+        trailing_zeros += 1;
+    }
+    let frac_len = decimals - trailing_zeros;
+
+    let total = int_len + if frac_len > 0 { 1 + frac_len } else { 0 };
+    if output.len() < total {
+        return Err(OutputBufferTooSmall);
+    }
+
+    let mut out = output.iter_mut();
+    for i in (decimals..decimals + int_len).rev() {
+        *out.next().unwrap() = digit_at(i);
+    }
+
+    if frac_len > 0 {
+        *out.next().unwrap() = b'.';
+        for i in (trailing_zeros..decimals).rev() {
+            *out.next().unwrap() = digit_at(i);
+        }
+    }
+
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(value: u128, decimals: usize) -> std::string::String {
+        let mut out = [0; 100];
+        let len = u256_to_decimal(&U256::from_u128(value), decimals, &mut out)
+            .unwrap_or_else(|_| panic!());
+        std::str::from_utf8(&out[..len]).unwrap().into()
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(format(0, 0), "0");
+        assert_eq!(format(7, 0), "7");
+        assert_eq!(format(1234567890, 0), "1234567890");
+        assert_eq!(
+            format(u128::MAX, 0),
+            "340282366920938463463374607431768211455"
+        );
+    }
+
+    #[test]
+    fn fractional() {
+        assert_eq!(format(0, 18), "0");
+        assert_eq!(format(1_250_000, 6), "1.25");
+        assert_eq!(format(5, 6), "0.000005");
+        assert_eq!(format(10_000_000_000_000_000, 18), "0.01");
+        assert_eq!(format(3_000_000_000_000_000_000, 18), "3");
+    }
+
+    #[test]
+    fn full_range() {
+        let mut out = [0; 100];
+        let max = U256([u64::MAX; 4]);
+        let len = u256_to_decimal(&max, 18, &mut out).unwrap_or_else(|_| panic!());
+        assert_eq!(
+            &out[..len],
+            &b"115792089237316195423570985008687907853269984665640564039457.584007913129639935"[..]
+        );
+
+        u256_to_decimal(&max, 0, &mut out[..77]).unwrap_err();
+    }
+}
@@ -0,0 +1,108 @@
+/*******************************************************************************
+*   (c) 2022 Zondax GmbH
+*
+*  Licensed under the Apache License, Version 2.0 (the "License");
+*  you may not use this file except in compliance with the License.
+*  You may obtain a copy of the License at
+*
+*      http://www.apache.org/licenses/LICENSE-2.0
+*
+*  Unless required by applicable law or agreed to in writing, software
+*  distributed under the License is distributed on an "AS IS" BASIS,
+*  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+*  See the License for the specific language governing permissions and
+*  limitations under the License.
+********************************************************************************/
+//! Responses larger than a single APDU
+//!
+//! A handler queues the whole response in `BUFFER` and sends the first chunk,
+//! with [`ApduError::MoreDataAvailable`] as status word if there's more to read.
+//! The host then reads the next chunks with `INS_GET_NEXT_CHUNK`, until the status word
+//! is [`ApduError::Success`].
+use bolos::{lazy_static, PIC};
+
+use crate::{
+    constants::ApduError,
+    handlers::resources::{BUFFERAccessors, BUFFER},
+};
+
+use super::Uploader;
+
+/// Number of bytes of the response already sent
+#[lazy_static]
+static mut SENT: usize = 0;
+
+pub struct Response;
+
+impl From<Response> for BUFFERAccessors {
+    fn from(_: Response) -> Self {
+        Self::Response
+    }
+}
+
+impl Response {
+    /// Maximum number of bytes sent with each APDU
+    pub const CHUNK_LEN: usize = 0xF0;
+
+    /// Starts a new response
+    ///
+    /// `BUFFER` is taken over, discarding whatever it held
+    /// (a previous response not read entirely, or an upload)
+    pub fn start() -> Result<(), ApduError> {
+        let zbuffer = unsafe { BUFFER.lock(Self)? };
+        zbuffer.reset();
+
+        unsafe {
+            *SENT = 0;
+        }
+
+        Ok(())
+    }
+
+    /// Appends `data` to the response
+    pub fn push(data: &[u8]) -> Result<(), ApduError> {
+        let zbuffer = unsafe { BUFFER.acquire(Self)? };
+
+        zbuffer.write(data).map_err(|_| ApduError::ExecutionError)
+    }
+
+    /// Writes the next chunk of the response to `out`
+    ///
+    /// Returns the number of bytes written and the status word to send with them:
+    /// [`ApduError::MoreDataAvailable`], or [`ApduError::Success`] for the last chunk,
+    /// after which the response is discarded
+    #[inline(never)]
+    pub fn next_chunk(out: &mut [u8]) -> Result<(usize, ApduError), ApduError> {
+        //nothing queued, or BUFFER was taken over since
+        let zbuffer =
+            unsafe { BUFFER.acquire(Self) }.map_err(|_| ApduError::ApduCodeEmptyBuffer)?;
+        let data = zbuffer.read_exact();
+
+        let start = core::cmp::min(unsafe { *SENT }, data.len());
+        let end = core::cmp::min(start + Self::CHUNK_LEN, data.len());
+        let chunk = &data[start..end];
+
+        out.get_mut(..chunk.len())
+            .ok_or(ApduError::OutputBufferTooSmall)?
+            .copy_from_slice(chunk);
+
+        if end == data.len() {
+            Self::discard();
+            Ok((chunk.len(), ApduError::Success))
+        } else {
+            unsafe {
+                *SENT = end;
+            }
+            Ok((chunk.len(), ApduError::MoreDataAvailable))
+        }
+    }
+
+    /// Discards the response, releasing `BUFFER`
+    pub fn discard() {
+        unsafe {
+            *SENT = 0;
+        }
+
+        Uploader::release(Self);
+    }
+}
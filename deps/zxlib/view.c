@@ -41,6 +41,10 @@ void view_init(void) {
 #if defined(BLIND_SIGN_TOGGLE)
     blind_sign.toggle = true;
     h_blind_sign_update();
+#endif
+#if defined(TESTNET_TOGGLE)
+    testnet_sign.toggle = false;
+    h_testnet_sign_update();
 #endif
     UX_INIT();
 }
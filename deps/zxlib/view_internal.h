@@ -86,6 +86,18 @@ void h_blind_sign_toggle();
 void h_blind_sign_update();
 #endif
 
+#if defined(TESTNET_TOGGLE)
+typedef struct testnet_sign_toggle_t {
+  bool toggle;
+  char message[8 + 1];
+} testnet_sign_toggle_t;
+
+extern testnet_sign_toggle_t testnet_sign;
+
+void h_testnet_sign_toggle();
+void h_testnet_sign_update();
+#endif
+
 ///////////////////////////////////////////////
 ///////////////////////////////////////////////
 ///////////////////////////////////////////////
@@ -50,6 +50,9 @@ const ux_menu_entry_t menu_main[] = {
     {NULL, rs_h_expert_toggle, 0, &C_icon_app, "Expert mode:", BACKEND_LAZY.value, 33, 12},
 #if defined(BLIND_SIGN_TOGGLE)
     {NULL, h_blind_sign_toggle, 0, &C_icon_app, "Signing mode:", blind_sign.message, 33, 12},
+#endif
+#if defined(TESTNET_TOGGLE)
+    {NULL, h_testnet_sign_toggle, 0, &C_icon_app, "Testnets:", testnet_sign.message, 33, 12},
 #endif
     {NULL, NULL, 0, &C_icon_app, APPVERSION_LINE1, APPVERSION_LINE2, 33, 12},
 
@@ -188,6 +191,28 @@ void h_blind_sign_update() {
 }
 #endif
 
+#if defined(TESTNET_TOGGLE)
+testnet_sign_toggle_t testnet_sign;
+
+void h_testnet_sign_toggle() {
+    testnet_sign.toggle = !testnet_sign.toggle;
+    h_testnet_sign_update();
+#if defined(BLIND_SIGN_TOGGLE)
+    view_idle_show(3, NULL);
+#else
+    view_idle_show(2, NULL);
+#endif
+}
+
+void h_testnet_sign_update() {
+    if (testnet_sign.toggle) {
+        snprintf(testnet_sign.message, 9, "enabled");
+    } else {
+        snprintf(testnet_sign.message, 9, "disabled");
+    }
+}
+#endif
+
 /********* CRAPOLINES *************/
 
 void crapoline_ux_wait() {
@@ -50,6 +50,9 @@ UX_STEP_CB_INIT(ux_idle_flow_2_step, bn,  rs_h_expert_update(), rs_h_expert_togg
 #if defined(BLIND_SIGN_TOGGLE)
 UX_STEP_CB_INIT(ux_idle_flow_blind_toggle_step, bn,  h_blind_sign_update(), h_blind_sign_toggle(), { "Signing mode:", blind_sign.message, });
 #endif
+#if defined(TESTNET_TOGGLE)
+UX_STEP_CB_INIT(ux_idle_flow_testnet_toggle_step, bn,  h_testnet_sign_update(), h_testnet_sign_toggle(), { "Testnets:", testnet_sign.message, });
+#endif
 UX_STEP_NOCB(ux_idle_flow_3_step, bn, { APPVERSION_LINE1, APPVERSION_LINE2, });
 UX_STEP_NOCB(ux_idle_flow_4_step, bn, { "Developed by:", "Ledger", });
 UX_STEP_NOCB(ux_idle_flow_5_step, bn, { "License:", "Apache 2.0", });
@@ -60,6 +63,9 @@ const ux_flow_step_t *const ux_idle_flow [] = {
   &ux_idle_flow_2_step,
 #if defined(BLIND_SIGN_TOGGLE)
   &ux_idle_flow_blind_toggle_step,
+#endif
+#if defined(TESTNET_TOGGLE)
+  &ux_idle_flow_testnet_toggle_step,
 #endif
   &ux_idle_flow_3_step,
   &ux_idle_flow_4_step,
@@ -114,6 +120,22 @@ void h_blind_sign_update() {
 }
 #endif
 
+#if defined(TESTNET_TOGGLE)
+testnet_sign_toggle_t testnet_sign;
+
+void h_testnet_sign_toggle() {
+  testnet_sign.toggle = !testnet_sign.toggle;
+  ux_flow_init(0, ux_idle_flow, &ux_idle_flow_testnet_toggle_step);
+}
+
+void h_testnet_sign_update() {
+    snprintf(testnet_sign.message, 9, "disabled");
+    if (testnet_sign.toggle) {
+        snprintf(testnet_sign.message, 9, "enabled");
+    }
+}
+#endif
+
 //////////////////////////
 //////////////////////////
 //////////////////////////
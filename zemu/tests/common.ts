@@ -21,7 +21,25 @@ export const defaultOptions = {
   startText: 'DO NOT USE',
 }
 
-export const APP_DERIVATION = "m/2645'/579218131'/0'/0'"
+export const APP_DERIVATION = "m/2645'/1195502025'/1148870696'/0'/0'/0"
+
+export const CLA = 0xff
+
+export const INS = {
+  SIGN_INVOKE: 0x03,
+  SIGN_INVOKE_V3: 0x04,
+  SIGN_TYPED_DATA: 0x05,
+  SIGN_DEPLOY_ACCOUNT: 0x06,
+  SIGN_DEPLOY_ACCOUNT_V3: 0x07,
+  SIGN_DECLARE: 0x08,
+  SIGN_DECLARE_V3: 0x09,
+  SIGN_STARKEX_ORDER: 0x0a,
+  SIGN_STARKEX_TRANSFER: 0x0b,
+  RECOVER_PUBLIC_KEY: 0x0c,
+  GET_NEXT_CHUNK: 0x0d,
+  GET_APP_CONFIGURATION: 0x0e,
+  SIGN_FELT_BATCH: 0x21,
+}
 
 type MapCartesian<T extends any[][]> = {
   [P in keyof T]: T[P] extends Array<infer U> ? U : never
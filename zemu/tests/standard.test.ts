@@ -15,10 +15,10 @@
  ******************************************************************************* */
 
 import Zemu from '@zondax/zemu'
-import { APP_DERIVATION, cartesianProduct, defaultOptions, enableBlindSigning, models } from './common'
+import { APP_DERIVATION, CLA, INS, cartesianProduct, defaultOptions, enableBlindSigning, models } from './common'
 import Stark from '@ledgerhq/hw-app-starknet'
 
-import { ec as stark_ec } from 'starknet'
+import { ec as stark_ec, number } from 'starknet'
 
 describe.each(models)('Standard', function(m) {
   test('can start and stop container', async function() {
@@ -34,7 +34,7 @@ describe.each(models)('Standard', function(m) {
     const sim = new Zemu(m.path)
     try {
       await sim.start({ ...defaultOptions, model: m.name })
-      await sim.navigateAndCompareSnapshots('.', `${m.prefix.toLowerCase()}-mainmenu`, [1, 0, 0, 1, 0, 0, 4, -6])
+      await sim.navigateAndCompareSnapshots('.', `${m.prefix.toLowerCase()}-mainmenu`, [1, 0, 0, 1, 0, 0, 1, 0, 0, 4, -7])
     } finally {
       await sim.close()
     }
@@ -59,6 +59,50 @@ describe.each(models)('Standard', function(m) {
       await sim.close()
     }
   })
+
+  test('get app configuration', async function() {
+    const sim = new Zemu(m.path)
+    try {
+      await sim.start({ ...defaultOptions, model: m.name })
+      const resp = await sim.getTransport().send(CLA, INS.GET_APP_CONFIGURATION, 0, 0)
+
+      console.log(resp)
+
+      expect(resp.readUInt16BE(resp.length - 2)).toEqual(0x9000)
+      //format version
+      expect(resp[0]).toEqual(1)
+
+      //walk the entries to the supported instructions
+      let entries = resp.subarray(1, resp.length - 2)
+      let supported: Buffer | undefined
+      while (entries.length >= 2) {
+        const value = entries.subarray(2, 2 + entries[1])
+        if (entries[0] === 0x03) {
+          supported = value
+        }
+        entries = entries.subarray(2 + entries[1])
+      }
+
+      expect(supported).toBeDefined()
+      for (const ins of Object.values(INS)) {
+        expect(supported![ins >> 3] & (1 << (ins & 7))).not.toEqual(0)
+      }
+    } finally {
+      await sim.close()
+    }
+  })
+
+  test('get next chunk without a response', async function() {
+    const sim = new Zemu(m.path)
+    try {
+      await sim.start({ ...defaultOptions, model: m.name })
+      const resp = await sim.getTransport().send(CLA, INS.GET_NEXT_CHUNK, 0, 0, Buffer.alloc(0), [0x6982])
+
+      expect(resp.readUInt16BE(resp.length - 2)).toEqual(0x6982)
+    } finally {
+      await sim.close()
+    }
+  })
 })
 
 describe.skip.each(models)('Standard [%s] - pubkey', function(m) {
@@ -87,6 +131,7 @@ const SIGN_TEST_DATA = [
   {
     name: 'blind sign',
     nav: { s: [2, 0], x: [3, 0], sp: [3, 0] },
+    //the SHA-256 of this message has its top bits set
     op: Buffer.from('hello@zondax.ch'),
   },
 ]
@@ -123,7 +168,9 @@ describe.skip.each(models)('Standard [%s]; sign', function(m) {
       let signatureOK = true
       const keypair = stark_ec.getKeyPairFromPublicKey('0x' + resp_addr.publicKey.toString());
 
-      signatureOK = stark_ec.verify(keypair, '0x' + resp.hash, ['0x' + resp.r.toString('hex'), '0x' + resp.s.toString('hex')]);
+      //the whole 256-bit hash is signed, reduced modulo the curve order
+      const signedHash = number.toBN('0x' + resp.hash).umod(stark_ec.ec.n!)
+      signatureOK = stark_ec.verify(keypair, '0x' + signedHash.toString(16), ['0x' + resp.r.toString('hex'), '0x' + resp.s.toString('hex')]);
 
       expect(signatureOK).toEqual(true)
     } finally {
@@ -136,7 +183,8 @@ const FELT_TEST_DATA = [
   {
     name: 'random data',
     nav: { s: [2, 0], x: [3, 0], sp: [3, 0] },
-    felt: Buffer.alloc(32, 0x01), //no particular significance
+    //a felt using all of its 252 bits
+    felt: Buffer.concat([Buffer.from([0x07]), Buffer.alloc(31, 0xff)]),
   }
 ]
 